@@ -1,14 +1,14 @@
-#![feature(never_type)]
-use sscanf::sscanf;
-use std::str::FromStr;
+use advent_of_code::parse::{self, Cursor};
 
 advent_of_code::solution!(2);
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let result = input
-        .lines()
-        .flat_map(str::parse)
-        .filter_map(|game: Game| {
+pub fn part_one(input: &str) -> parse::Result<u32> {
+    let games = parse::lines(input)
+        .map(Game::parse)
+        .collect::<parse::Result<Vec<Game>>>()?;
+    let result = games
+        .into_iter()
+        .filter_map(|game| {
             if game.valid(12, 13, 14) {
                 Some(game.id)
             } else {
@@ -16,16 +16,15 @@ pub fn part_one(input: &str) -> Option<u32> {
             }
         })
         .sum();
-    Some(result)
+    Ok(result)
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let result = input
-        .lines()
-        .flat_map(str::parse)
-        .map(|game: Game| game.power())
-        .sum();
-    Some(result)
+pub fn part_two(input: &str) -> parse::Result<u32> {
+    let games = parse::lines(input)
+        .map(Game::parse)
+        .collect::<parse::Result<Vec<Game>>>()?;
+    let result = games.into_iter().map(|game| game.power()).sum();
+    Ok(result)
 }
 
 // Only need to store max number of red, green, and blue cubes
@@ -53,23 +52,23 @@ impl Game {
     fn power(&self) -> u32 {
         self.red * self.green * self.blue
     }
-}
-
-impl FromStr for Game {
-    type Err = !;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (id, rest) = sscanf!(s, "Game {}: {}", u32, String).expect("Game N: ...");
-        let mut game = Game::empty(id);
-        for round in rest.split("; ") {
-            for part in round.split(", ") {
-                let (n, color) = sscanf!(part, "{} {}", u32, String).expect("N red|green|blue");
-                match color.chars().next() {
-                    Some('r') => game.red = game.red.max(n),
-                    Some('g') => game.green = game.green.max(n),
-                    Some('b') => game.blue = game.blue.max(n),
-                    _ => panic!("unrecognized color {}", color),
-                }
+    fn parse(mut line: Cursor) -> parse::Result<Self> {
+        line.tag("Game")?;
+        let mut game = Game::empty(line.number()?);
+        line.tag(":")?;
+        for round in line.split(";") {
+            for mut draw in round.split(",") {
+                let n: u32 = draw.number()?;
+                let color = draw.trim_start();
+                let max = match draw.word()? {
+                    "red" => &mut game.red,
+                    "green" => &mut game.green,
+                    "blue" => &mut game.blue,
+                    other => return Err(color.error(format!("unrecognized color `{other}`"))),
+                };
+                *max = n.max(*max);
+                draw.end()?;
             }
         }
         Ok(game)
@@ -83,12 +82,12 @@ mod tests {
     #[test]
     fn test_part_one() {
         let result = part_one(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(8));
+        assert_eq!(result, Ok(8));
     }
 
     #[test]
     fn test_part_two() {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(2286));
+        assert_eq!(result, Ok(2286));
     }
 }
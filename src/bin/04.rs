@@ -1,12 +1,12 @@
-#![feature(never_type)]
-
+use advent_of_code::parse::{self, Cursor};
 use std::collections::HashSet;
-use std::str::FromStr;
 
 advent_of_code::solution!(4);
 
-pub fn part_one(input: &str) -> Option<u64> {
-    let cards: Vec<Card> = input.lines().flat_map(str::parse).collect();
+pub fn part_one(input: &str) -> parse::Result<u64> {
+    let cards = parse::lines(input)
+        .map(Card::parse)
+        .collect::<parse::Result<Vec<Card>>>()?;
     let total = cards
         .into_iter()
         .map(|card| {
@@ -18,18 +18,20 @@ pub fn part_one(input: &str) -> Option<u64> {
             }
         })
         .sum();
-    Some(total)
+    Ok(total)
 }
 
-pub fn part_two(input: &str) -> Option<u64> {
-    let cards: Vec<Card> = input.lines().flat_map(str::parse).collect();
+pub fn part_two(input: &str) -> parse::Result<u64> {
+    let cards = parse::lines(input)
+        .map(Card::parse)
+        .collect::<parse::Result<Vec<Card>>>()?;
     let mut counts = vec![1; cards.len()];
     for (i, card) in cards.into_iter().enumerate() {
         for j in 0..card.wins() {
             counts[i + j + 1] += counts[i];
         }
     }
-    Some(counts.into_iter().sum())
+    Ok(counts.into_iter().sum())
 }
 
 struct Card {
@@ -44,16 +46,13 @@ impl Card {
             .filter(|x| self.winners.contains(x))
             .count()
     }
-}
-
-impl FromStr for Card {
-    type Err = !;
 
-    fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let (_, rest) = input.split_once(':').unwrap();
-        let (before, after) = rest.split_once('|').unwrap();
-        let winners = before.split_whitespace().flat_map(str::parse).collect();
-        let given = after.split_whitespace().flat_map(str::parse).collect();
+    fn parse(mut line: Cursor) -> parse::Result<Self> {
+        line.tag("Card")?;
+        line.number::<u32>()?;
+        line.tag(":")?;
+        let winners = line.until("|")?.numbers()?.into_iter().collect();
+        let given = line.numbers()?;
         Ok(Card { winners, given })
     }
 }
@@ -65,12 +64,12 @@ mod tests {
     #[test]
     fn test_part_one() {
         let result = part_one(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(13));
+        assert_eq!(result, Ok(13));
     }
 
     #[test]
     fn test_part_two() {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(30));
+        assert_eq!(result, Ok(30));
     }
 }
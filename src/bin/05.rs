@@ -1,149 +1,310 @@
-#![feature(never_type)]
-use sscanf::sscanf;
+use advent_of_code::parse::{self, Cursor, ParseError};
+use advent_of_code::template::runner::mode_arg;
+use std::fmt;
 use std::str::FromStr;
 
-advent_of_code::solution!(5);
+advent_of_code::solution!(5, "breakpoints" => breakpoints, "inverse" => inverse);
 
-pub fn part_one(input: &str) -> Option<u64> {
-    let almanac: Almanac = str::parse(input).unwrap();
+pub fn part_one(input: &str) -> Result<u128, AlmanacError> {
+    let almanac: Almanac = str::parse(input)?;
+    let map = almanac.compose();
     almanac
         .seeds
-        .into_iter()
-        .map(|seed| {
-            almanac.mappings.iter().fold(seed, |acc, mapping| {
-                mapping
-                    .iter()
-                    .copied()
-                    .find_map(|(dst, lo, len)| {
-                        let hi = lo + len - 1;
-                        if lo <= acc && acc <= hi {
-                            Some(acc - lo + dst)
-                        } else {
-                            None
-                        }
-                    })
-                    .unwrap_or(acc)
-            })
-        })
+        .iter()
+        .map(|&seed| map.get(seed.into()))
         .min()
+        .ok_or(AlmanacError::NoSeeds)
 }
 
-pub fn part_two(input: &str) -> Option<u64> {
-    let almanac: Almanac = str::parse(input).unwrap();
-    let mut ranges: Vec<(u64, u64)> = almanac
-        .seeds
-        .chunks(2)
-        .flat_map(TryInto::<[u64; 2]>::try_into)
-        .map(|[lo, len]| (lo, lo + len - 1))
+pub fn part_two(input: &str) -> Result<u128, AlmanacError> {
+    let almanac: Almanac = str::parse(input)?;
+    let map = almanac.compose();
+    almanac
+        .seed_ranges()
+        .flat_map(|range| map.min_image(range))
+        .min()
+        .ok_or(AlmanacError::NoSeeds)
+}
+
+// The whole seed to location map, one piece per line
+fn breakpoints(input: &str) -> Result<String, AlmanacError> {
+    let almanac: Almanac = str::parse(input)?;
+    Ok(almanac.compose().to_string())
+}
+
+// The seeds that end up at the locations passed with `--arg`, either one
+// location or a range like `40..50`. Without one, the seeds behind each
+// stretch of locations the map reaches, lowest first.
+fn inverse(input: &str) -> Result<String, AlmanacError> {
+    let almanac: Almanac = str::parse(input)?;
+    let map = almanac.compose();
+    if let Some(query) = mode_arg() {
+        let range = parse_query(&query).ok_or(AlmanacError::Query(query))?;
+        return Ok(format!("{:?}", map.preimage(range)));
+    }
+
+    let mut images: Vec<Range> = map
+        .segments()
+        .map(|(range, offset)| shift(range.start, offset)..shift(range.end, offset))
+        .collect();
+    images.sort_by_key(|image| (image.start, image.end));
+    images.dedup();
+    let lines: Vec<_> = images
+        .into_iter()
+        .map(|image| format!("{image:?} <- {:?}", map.preimage(image.clone())))
         .collect();
+    Ok(lines.join("\n"))
+}
 
-    for mapping in almanac.mappings.iter() {
-        ranges = ranges
-            .iter()
-            .copied()
-            .flat_map(|(lo, hi)| {
-                // suppose we have an interval like this:
-                //
-                // |------------------------------|
-                //
-                // with mappings like this:
-                //      |=====|
-                //                  |========|
-                //
-                // we'll gradually move the head of the interval forward:
-                //
-                // |----|
-                // ^    |=====|
-                //            |-----|
-                // ---------> ^     |========|
-                //                           |----|
-                //            -------------> ^
-                // until we've exhausted the mappings or the head of the
-                // interval crosses its upper bound, remapping the
-                // parts of it that overlapped the mappings.
-                //
-                // |----|=====|-----|========|----|
-                //                           ^
-                let mut head = lo;
-                let mut next = Vec::new();
-                for (dst, src, len) in mapping.iter().copied() {
-                    let end = src + len - 1;
-
-                    // mapping does not intersect this interval, but a
-                    // later one could
-                    if head > end {
-                        continue;
-                    }
-
-                    // no latter mapping can intersect this interval;
-                    // src will always be higher
-                    if src > hi {
-                        break;
-                    }
-
-                    // exhausted interval, the head has crossed the
-                    // upper bound
-                    if head > hi {
-                        break;
-                    }
-
-                    // slice off the prefix of the interval before the
-                    // lower bound of this mapping
-                    if src > head {
-                        next.push((head, src - 1));
-                    }
-
-                    // remap the overlapping section
-                    let mapped_lo = head.max(src) - src + dst;
-                    let mapped_hi = hi.min(end) - src + dst;
-                    next.push((mapped_lo, mapped_hi));
-
-                    // then move the head to point after it
-                    head = hi.min(end) + 1;
-                }
-
-                // remainder of the interval after the upper bound of
-                // any mapping
-                if head < hi {
-                    next.push((head, hi));
-                }
-                next
+fn parse_query(query: &str) -> Option<Range> {
+    match query.split_once("..") {
+        Some((start, end)) => Some(start.trim().parse().ok()?..end.trim().parse().ok()?),
+        None => {
+            let location = query.trim().parse().ok()?;
+            Some(location..location + 1)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AlmanacError {
+    Parse(ParseError),
+    // no seeds, or only empty ranges of them
+    NoSeeds,
+    // what was passed in place of a location or range
+    Query(String),
+}
+
+impl From<ParseError> for AlmanacError {
+    fn from(error: ParseError) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl fmt::Display for AlmanacError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(error) => write!(f, "{error}"),
+            Self::NoSeeds => write!(f, "no seeds to plant"),
+            Self::Query(query) => write!(
+                f,
+                "expecting a location or range like `40..50`, not `{query}`"
+            ),
+        }
+    }
+}
+
+type Range = std::ops::Range<u128>;
+
+// Past any u64 shifted by any u64 length, so no mapped value falls off the
+// end of a map and no range end wraps.
+const END: u128 = 1 << 65;
+
+// A piecewise shift: each piece covers the values from its start up to the
+// next piece's start (or `END`), and maps them by adding its offset.
+struct Map {
+    pieces: Vec<Piece>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Piece {
+    start: u128,
+    offset: i128,
+}
+
+fn shift(value: u128, offset: i128) -> u128 {
+    (value as i128 + offset) as u128
+}
+
+impl Map {
+    fn identity() -> Self {
+        Self {
+            pieces: vec![Piece {
+                start: 0,
+                offset: 0,
+            }],
+        }
+    }
+
+    // From `(dst, src, len)` lines, where values outside every source range
+    // map to themselves. If source ranges overlap, the one that starts lowest
+    // wins.
+    fn from_lines(lines: &[(u64, u64, u64)]) -> Self {
+        let mut lines = lines.to_vec();
+        lines.sort_by_key(|&(_, src, _)| src);
+
+        let mut pieces = Vec::new();
+        let mut cursor = 0;
+        for (dst, src, len) in lines {
+            let start = cursor.max(src.into());
+            let end = u128::from(src) + u128::from(len);
+            if start >= end {
+                continue;
+            }
+            if start > cursor {
+                pieces.push(Piece {
+                    start: cursor,
+                    offset: 0,
+                });
+            }
+            pieces.push(Piece {
+                start,
+                offset: i128::from(dst) - i128::from(src),
+            });
+            cursor = end;
+        }
+        pieces.push(Piece {
+            start: cursor,
+            offset: 0,
+        });
+        Self::merged(pieces)
+    }
+
+    // Joins neighbouring pieces with the same offset
+    fn merged(pieces: Vec<Piece>) -> Self {
+        let mut merged: Vec<Piece> = Vec::with_capacity(pieces.len());
+        for piece in pieces {
+            match merged.last() {
+                Some(last) if last.offset == piece.offset => (),
+                _ => merged.push(piece),
+            }
+        }
+        Self { pieces: merged }
+    }
+
+    fn segments(&self) -> impl Iterator<Item = (Range, i128)> + '_ {
+        self.pieces.iter().enumerate().map(|(i, piece)| {
+            let end = self.pieces.get(i + 1).map_or(END, |next| next.start);
+            (piece.start..end, piece.offset)
+        })
+    }
+
+    // The segments clipped to `range`
+    fn overlapping(&self, range: Range) -> impl Iterator<Item = (Range, i128)> + '_ {
+        // the first piece that ends after `range.start`
+        let first = self
+            .pieces
+            .partition_point(|piece| piece.start <= range.start);
+        self.segments()
+            .skip(first.saturating_sub(1))
+            .take_while(move |(segment, _)| segment.start < range.end)
+            .map(move |(segment, offset)| {
+                (
+                    segment.start.max(range.start)..segment.end.min(range.end),
+                    offset,
+                )
+            })
+            .filter(|(segment, _)| !segment.is_empty())
+    }
+
+    fn get(&self, value: u128) -> u128 {
+        let (_, offset) = self.overlapping(value..value + 1).next().unwrap();
+        shift(value, offset)
+    }
+
+    // `self` followed by `next`
+    fn then(&self, next: &Map) -> Map {
+        let mut pieces = Vec::new();
+        for (range, offset) in self.segments() {
+            let image = shift(range.start, offset)..shift(range.end, offset);
+            for (part, next_offset) in next.overlapping(image) {
+                pieces.push(Piece {
+                    start: shift(part.start, -offset),
+                    offset: offset + next_offset,
+                });
+            }
+        }
+        Self::merged(pieces)
+    }
+
+    fn min_image(&self, range: Range) -> Option<u128> {
+        self.overlapping(range)
+            .map(|(part, offset)| shift(part.start, offset))
+            .min()
+    }
+
+    // Every value that maps into `range`, as sorted disjoint ranges
+    fn preimage(&self, range: Range) -> Vec<Range> {
+        let mut parts: Vec<Range> = self
+            .segments()
+            .flat_map(|(segment, offset)| {
+                let start = shift(segment.start, offset).max(range.start);
+                let end = shift(segment.end, offset).min(range.end);
+                (start < end).then(|| shift(start, -offset)..shift(end, -offset))
             })
             .collect();
+        parts.sort_by_key(|part| part.start);
+
+        let mut merged: Vec<Range> = Vec::with_capacity(parts.len());
+        for part in parts {
+            match merged.last_mut() {
+                Some(last) if last.end == part.start => last.end = part.end,
+                _ => merged.push(part),
+            }
+        }
+        merged
     }
+}
 
-    ranges.into_iter().map(|(lo, _)| lo).min()
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (range, offset)) in self.segments().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let image = shift(range.start, offset)..shift(range.end, offset);
+            write!(f, "{range:?} -> {image:?} ({offset:+})")?;
+        }
+        Ok(())
+    }
 }
 
 struct Almanac {
     seeds: Vec<u64>,
-    mappings: Vec<Vec<(u64, u64, u64)>>,
+    mappings: Vec<Map>,
 }
 
-impl FromStr for Almanac {
-    type Err = !;
+impl Almanac {
+    fn compose(&self) -> Map {
+        self.mappings
+            .iter()
+            .fold(Map::identity(), |map, mapping| map.then(mapping))
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (seeds_str, rest) = s.split_once("\n\n").unwrap();
+    fn seed_ranges(&self) -> impl Iterator<Item = Range> + '_ {
+        self.seeds
+            .chunks_exact(2)
+            .map(|pair| u128::from(pair[0])..u128::from(pair[0]) + u128::from(pair[1]))
+    }
+}
 
-        let seeds = sscanf!(seeds_str, "seeds: {}", String)
-            .unwrap()
-            .split_whitespace()
-            .flat_map(str::parse)
-            .collect();
+impl FromStr for Almanac {
+    type Err = ParseError;
 
-        let mappings: Vec<Vec<(u64, u64, u64)>> = rest
-            .split("\n\n")
-            .map(|block| {
-                let mut mapping: Vec<(u64, u64, u64)> = block
+    fn from_str(s: &str) -> parse::Result<Self> {
+        let mut blocks = parse::blocks(s);
+        let mut seeds = blocks
+            .next()
+            .ok_or_else(|| Cursor::new(s).error("expecting seeds"))?;
+        seeds.tag("seeds:")?;
+        let seeds = seeds.numbers()?;
+
+        let mappings = blocks
+            .map(|mut block| {
+                let mut header = block.line()?;
+                header.until("map:")?;
+                header.end()?;
+                let lines = block
                     .lines()
-                    .skip(1)
-                    .flat_map(|line| sscanf!(line, "{} {} {}", u64, u64, u64))
-                    .collect();
-                mapping.sort_by_key(|&(_, src, _)| src);
-                mapping
+                    .map(|mut line| {
+                        let range = (line.number()?, line.number()?, line.number()?);
+                        line.end()?;
+                        Ok(range)
+                    })
+                    .collect::<parse::Result<Vec<_>>>()?;
+                Ok(Map::from_lines(&lines))
             })
-            .collect();
+            .collect::<parse::Result<_>>()?;
 
         Ok(Almanac { seeds, mappings })
     }
@@ -156,12 +317,57 @@ mod tests {
     #[test]
     fn test_part_one() {
         let result = part_one(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(35));
+        assert_eq!(result, Ok(35));
     }
 
     #[test]
     fn test_part_two() {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(46));
+        assert_eq!(result, Ok(46));
+    }
+
+    #[test]
+    fn test_preimage() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let map = Almanac::from_str(&input).unwrap().compose();
+        for (seed, location) in [(79, 82), (14, 43), (55, 86), (13, 35), (82, 46)] {
+            assert_eq!(map.get(seed), location);
+            assert!(map
+                .preimage(location..location + 1)
+                .iter()
+                .any(|range| range.contains(&seed)));
+        }
+
+        let query = parse_query("1..19").unwrap();
+        assert_eq!(map.preimage(query), vec![26..44]);
+        let query = parse_query("46").unwrap();
+        assert!(map.preimage(query).iter().any(|range| range.contains(&82)));
+        assert_eq!(parse_query("x..3"), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = part_one("seeds: 79 x\n\nseed-to-soil map:\n50 98 2\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 1, column 11: expecting a number");
+        let error = part_one("seeds: 79\n\nseed-to-soil map:\n50 98 2\n52 50\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 5, column 6: expecting a number");
+        let error = part_one("seeds: 79\n\nseed-to-soil:\n50 98 2\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 3, column 1: expecting `map:`");
+        assert_eq!(part_one("seeds:\n"), Err(AlmanacError::NoSeeds));
+    }
+
+    #[test]
+    fn test_range_ends() {
+        // a mapping that runs right up to the end of u64
+        let map = Map::from_lines(&[(0, u64::MAX - 9, 10)]);
+        let max = u128::from(u64::MAX);
+        assert_eq!(map.get(max), 9);
+        assert_eq!(map.get(max - 10), max - 10);
+        assert_eq!(map.min_image(max - 20..max + 1), Some(0));
+
+        // where two source ranges overlap, the one starting lowest wins
+        let map = Map::from_lines(&[(100, 10, 10), (200, 5, 10)]);
+        assert_eq!(map.get(12), 207);
+        assert_eq!(map.get(17), 107);
     }
 }
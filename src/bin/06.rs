@@ -1,64 +1,104 @@
-#![feature(never_type)]
-
 advent_of_code::solution!(6);
 
-pub fn part_one(input: &str) -> Option<u64> {
+use advent_of_code::parse::{self, Cursor};
+use num::integer::Roots;
+use num::{BigUint, Integer};
+
+pub fn part_one(input: &str) -> parse::Result<BigUint> {
     let races = parse(Mode::Split, input)?;
     let result = races
-        .into_iter()
-        .map(|(time, dist)| calculate_hold(time, dist))
+        .iter()
+        .map(|(time, dist)| winning_holds(time, dist))
         .product();
-    Some(result)
+    Ok(result)
 }
 
-pub fn part_two(input: &str) -> Option<u64> {
+pub fn part_two(input: &str) -> parse::Result<BigUint> {
     let races = parse(Mode::Join, input)?;
     let result = races
-        .into_iter()
-        .map(|(time, dist)| calculate_hold(time, dist))
+        .iter()
+        .map(|(time, dist)| winning_holds(time, dist))
         .product();
-    Some(result)
+    Ok(result)
 }
 
-fn calculate_hold(time: u64, dist: u64) -> u64 {
-    // We know:
-    //
-    //   dist = hold * (time - hold)
-    //
-    // or
-    //
-    //   -hold² + time * hold - dist = 0
-    //
-    // We can plug this into the quadratic formula; the negative signs
-    // cancel:
-    //
-    //        time ± √(time² - 4dist)
-    // hold = -----------------------
-    //                 2
-    //
-    // We only care about integer roots, so round the low root down and
-    // the high root up.
-    let t = time as f64;
-    let d = dist as f64;
-    let root = (t * t - 4.0 * d).sqrt();
-    let lo = ((t - root) / 2.0).floor();
-    let hi = ((t + root) / 2.0).ceil();
-    (hi - lo) as u64 - 1
+// How many whole holds beat `dist`.
+//
+// We know:
+//
+//   dist = hold * (time - hold)
+//
+// or
+//
+//   -hold² + time * hold - dist = 0
+//
+// We can plug this into the quadratic formula; the negative signs
+// cancel:
+//
+//        time ± √(time² - 4dist)
+// hold = -----------------------
+//                 2
+//
+// The integer square root puts the low root within a step of the first
+// winning hold, which is then checked exactly. The winning holds are
+// symmetric around `time / 2`, so the last one mirrors the first.
+fn winning_holds<T: Integer + Roots + Clone>(time: &T, dist: &T) -> T {
+    let two = T::one() + T::one();
+    let beats = |hold: &T| hold.clone() * (time.clone() - hold.clone()) > *dist;
+
+    let square = time.clone() * time.clone();
+    let four_dist = dist.clone() * two.clone() * two.clone();
+    if square <= four_dist {
+        return T::zero();
+    }
+    let root = (square - four_dist).sqrt();
+
+    let half = time.clone() / two.clone();
+    let mut first = (time.clone() - root) / two.clone();
+    while first > T::zero() && beats(&(first.clone() - T::one())) {
+        first = first - T::one();
+    }
+    while first <= half && !beats(&first) {
+        first = first + T::one();
+    }
+    if first > half {
+        return T::zero();
+    }
+    time.clone() - first * two + T::one()
 }
 
-fn parse(mode: Mode, input: &str) -> Option<Vec<(u64, u64)>> {
-    let [times, dists]: [&str; 2] = input.lines().collect::<Vec<&str>>().try_into().ok()?;
-    let times = parse_prefixed(mode, "Time:", times)?;
-    let dists = parse_prefixed(mode, "Distance:", dists)?;
-    Some(times.into_iter().zip(dists).collect())
+fn parse(mode: Mode, input: &str) -> parse::Result<Vec<(BigUint, BigUint)>> {
+    let mut cursor = Cursor::new(input);
+    let times = parse_prefixed(mode, "Time:", cursor.line()?)?;
+    let line = cursor.line()?;
+    let dists = parse_prefixed(mode, "Distance:", line)?;
+    cursor.end()?;
+    if dists.len() != times.len() {
+        return Err(line.error(format!(
+            "{} distances for {} times",
+            dists.len(),
+            times.len()
+        )));
+    }
+    Ok(times.into_iter().zip(dists).collect())
 }
 
-fn parse_prefixed(mode: Mode, prefix: &str, input: &str) -> Option<Vec<u64>> {
-    let split = input.strip_prefix(prefix)?.split_whitespace();
-    Some(match mode {
-        Mode::Split => split.flat_map(str::parse).collect(),
-        Mode::Join => vec![split.collect::<Vec<_>>().join("").parse().ok()?],
-    })
+fn parse_prefixed(mode: Mode, prefix: &str, mut line: Cursor) -> parse::Result<Vec<BigUint>> {
+    line.tag(prefix)?;
+    match mode {
+        Mode::Split => line.numbers(),
+        Mode::Join => {
+            let start = line.trim_start();
+            let mut digits = String::new();
+            while !line.is_empty() {
+                digits.push_str(line.word()?);
+            }
+            let number = digits
+                .parse()
+                .map_err(|_| start.error("expecting a number"))?;
+            Ok(vec![number])
+        }
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -74,12 +114,71 @@ mod tests {
     #[test]
     fn test_part_one() {
         let result = part_one(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(288));
+        assert_eq!(result, Ok(BigUint::from(288u32)));
     }
 
     #[test]
     fn test_part_two() {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(71503));
+        assert_eq!(result, Ok(BigUint::from(71503u32)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = part_one("Time: 7 15 x\nDistance: 9 40 200").unwrap_err();
+        assert_eq!(error.to_string(), "line 1, column 12: expecting a number");
+        let error = part_one("Time: 7 15 30\nDistance: 9 40").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 1: 2 distances for 3 times"
+        );
+        let error = part_two("Time: 7 15 30\nDistance: 9 4x0 200").unwrap_err();
+        assert_eq!(error.to_string(), "line 2, column 11: expecting a number");
+        let error = part_two("Time: 7 15 30").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 1, column 14: unexpected end of input"
+        );
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        for time in 0..=80u128 {
+            // up to just past the best possible distance
+            for dist in 0..=time * time / 4 + 1 {
+                let expected = (0..=time)
+                    .filter(|hold| hold * (time - hold) > dist)
+                    .count();
+                assert_eq!(
+                    winning_holds(&time, &dist),
+                    expected as u128,
+                    "time {time}, dist {dist}"
+                );
+                assert_eq!(
+                    winning_holds(&BigUint::from(time), &BigUint::from(dist)),
+                    BigUint::from(expected),
+                    "time {time}, dist {dist}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_huge_races() {
+        // past 2^53 a float can't tell these distances apart, but only the
+        // first is beaten, and only by the middle hold
+        let time: u128 = (1 << 60) + 2;
+        let half = time / 2;
+        assert_eq!(winning_holds(&time, &(half * half - 1)), 1);
+        assert_eq!(winning_holds(&time, &(half * half)), 0);
+
+        // a time and distance far past u128, as part two would join them
+        let input = format!("Time: {}\nDistance: {}", "9".repeat(60), "1".repeat(100));
+        let time: BigUint = "9".repeat(60).parse().unwrap();
+        let dist: BigUint = "1".repeat(100).parse().unwrap();
+        let result = part_two(&input).unwrap();
+        let first = (&time - &result + 1u32) / 2u32;
+        assert!(&first * (&time - &first) > dist);
+        assert!((&first - 1u32) * (&time - &first + 1u32) <= dist);
     }
 }
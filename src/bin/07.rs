@@ -1,130 +1,167 @@
-use sscanf::sscanf;
+use advent_of_code::parse::{self, Cursor};
 
-advent_of_code::solution!(7);
+advent_of_code::solution!(7, "wild-twos" => wild_twos, "strongest-first" => strongest_first);
 
-pub fn part_one(input: &str) -> Option<u32> {
-    run(Mode::Jack, input)
+pub fn part_one(input: &str) -> parse::Result<u32> {
+    run(&STANDARD, input)
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    run(Mode::Joker, input)
+pub fn part_two(input: &str) -> parse::Result<u32> {
+    run(&JOKERS, input)
 }
 
-fn run(mode: Mode, input: &str) -> Option<u32> {
-    let mut hands = input
-        .lines()
-        .map(|line| Hand::parse(mode, line))
-        .collect::<Option<Vec<Hand>>>()?;
+// Part two with twos wild as well as jokers
+fn wild_twos(input: &str) -> parse::Result<u32> {
+    run(&WILD_TWOS, input)
+}
+
+// Part two breaking ties on the strongest card rather than the first
+fn strongest_first(input: &str) -> parse::Result<u32> {
+    run(&STRONGEST_FIRST, input)
+}
+
+fn run(rules: &Rules, input: &str) -> parse::Result<u32> {
+    let mut hands = parse::lines(input)
+        .map(|line| Hand::parse(rules, line))
+        .collect::<parse::Result<Vec<Hand>>>()?;
     hands.sort();
     let score = hands
         .into_iter()
         .enumerate()
         .map(|(i, hand)| (i + 1) as u32 * hand.bid)
         .sum();
-    Some(score)
+    Ok(score)
 }
 
-#[derive(PartialEq, PartialOrd, Eq, Ord, Clone, Copy, Debug)]
-struct Hand {
-    kind: Kind,
-    cards: [u8; 5],
-    bid: u32,
+// Hand shapes from weakest to strongest, as the sizes of each group of equal
+// cards from largest to smallest
+const CATEGORIES: &[&[usize]] = &[
+    &[1, 1, 1, 1, 1], // high card
+    &[2, 1, 1, 1],    // one pair
+    &[2, 2, 1],       // two pairs
+    &[3, 1, 1],       // three of a kind
+    &[3, 2],          // full house
+    &[4, 1],          // four of a kind
+    &[5],             // five of a kind
+];
+
+const STANDARD: Rules = Rules {
+    ranking: "23456789TJQKA",
+    wild: "",
+    hand_size: 5,
+    categories: CATEGORIES,
+    tie_break: TieBreak::Dealt,
+};
+
+const JOKERS: Rules = Rules {
+    ranking: "J23456789TQKA",
+    wild: "J",
+    ..STANDARD
+};
+
+const WILD_TWOS: Rules = Rules {
+    wild: "J2",
+    ..JOKERS
+};
+
+const STRONGEST_FIRST: Rules = Rules {
+    tie_break: TieBreak::Strongest,
+    ..JOKERS
+};
+
+struct Rules<'a> {
+    // cards from weakest to strongest
+    ranking: &'a str,
+    // cards that join whichever group makes the hand strongest
+    wild: &'a str,
+    hand_size: usize,
+    // shapes that aren't listed rank below all of these
+    categories: &'a [&'a [usize]],
+    tie_break: TieBreak,
 }
 
-impl Hand {
-    fn parse(mode: Mode, input: &str) -> Option<Self> {
-        let (cards, bid) = sscanf!(input, "{} {}", String, u32).ok()?;
-        let cards = cards
+// The order cards are compared in between hands of the same category
+#[derive(Clone, Copy)]
+enum TieBreak {
+    // as they were dealt
+    Dealt,
+    // strongest first
+    Strongest,
+}
+
+impl Rules<'_> {
+    fn rank(&self, card: char) -> Option<u8> {
+        self.ranking
             .chars()
-            .flat_map(|c| parse_card(mode, c))
-            .collect::<Vec<_>>()
+            .position(|c| c == card)?
             .try_into()
-            .ok()?;
-        let kind = categorize(cards);
-        Some(Self { kind, cards, bid })
+            .ok()
     }
-}
 
-#[allow(clippy::enum_variant_names)]
-#[derive(PartialEq, PartialOrd, Eq, Ord, Clone, Copy, Debug)]
-enum Kind {
-    HighCard,
-    OnePair,
-    TwoPairs,
-    ThreeOfAKind,
-    FullHouse,
-    FourOfAKind,
-    FiveOfAKind,
-}
-
-fn categorize(cards: [u8; 5]) -> Kind {
-    let mut hist = [0; 15];
-    cards.iter().copied().for_each(|card| {
-        hist[card as usize] += 1;
-    });
-
-    if hist[JOKER] > 0 {
-        let card = hist
-            .iter()
-            .enumerate()
-            .filter(|(card, _)| *card != JOKER)
-            .max_by_key(|(_, count)| *count)
-            .map(|(card, _)| card)
-            .unwrap_or(JOKER);
-
-        if card != JOKER {
-            hist[card] += hist[JOKER];
-            hist[JOKER] = 0;
+    // Where the hand's shape is in `categories`, counting from 1
+    fn categorize(&self, cards: &str) -> usize {
+        let mut groups: Vec<usize> = Vec::new();
+        let mut seen = String::new();
+        let mut wild = 0;
+        for card in cards.chars() {
+            if self.wild.contains(card) {
+                wild += 1;
+            } else if !seen.contains(card) {
+                seen.push(card);
+                groups.push(cards.matches(card).count());
+            }
+        }
+        groups.sort_by(|a, b| b.cmp(a));
+        match groups.first_mut() {
+            Some(largest) => *largest += wild,
+            None => groups.push(wild),
         }
-    }
 
-    let mut counts = hist
-        .into_iter()
-        .filter(|count| *count != 0)
-        .collect::<Vec<_>>();
-    counts.sort();
-
-    match &counts[..] {
-        [5] => Kind::FiveOfAKind,
-        [1, 4] => Kind::FourOfAKind,
-        [2, 3] => Kind::FullHouse,
-        [1, 1, 3] => Kind::ThreeOfAKind,
-        [1, 2, 2] => Kind::TwoPairs,
-        [1, 1, 1, 2] => Kind::OnePair,
-        _ => Kind::HighCard,
+        self.categories
+            .iter()
+            .position(|category| *category == groups)
+            .map_or(0, |position| position + 1)
     }
 }
 
-const JOKER: usize = 0;
-const JACK: usize = 11;
-
-fn parse_card(mode: Mode, c: char) -> Option<u8> {
-    let card = match c {
-        '2' => 2,
-        '3' => 3,
-        '4' => 4,
-        '5' => 5,
-        '6' => 6,
-        '7' => 7,
-        '8' => 8,
-        '9' => 9,
-        'T' => 10,
-        'J' => match mode {
-            Mode::Joker => JOKER as u8,
-            Mode::Jack => JACK as u8,
-        },
-        'Q' => 12,
-        'K' => 13,
-        'A' => 14,
-        _ => return None,
-    };
-    Some(card)
+#[derive(PartialEq, PartialOrd, Eq, Ord, Clone, Debug)]
+struct Hand {
+    kind: usize,
+    // ranks in tie-break order
+    cards: Vec<u8>,
+    bid: u32,
 }
 
-#[derive(Clone, Copy)]
-enum Mode {
-    Jack,
-    Joker,
+impl Hand {
+    fn parse(rules: &Rules, mut line: Cursor) -> parse::Result<Self> {
+        let start = line.trim_start();
+        let cards = line.word()?;
+        let count = cards.chars().count();
+        if count != rules.hand_size {
+            return Err(start.error(format!(
+                "hand has {count} cards, expecting {}",
+                rules.hand_size
+            )));
+        }
+        let mut ranks = cards
+            .chars()
+            .map(|card| {
+                rules
+                    .rank(card)
+                    .ok_or_else(|| start.error(format!("unrecognized card `{card}`")))
+            })
+            .collect::<parse::Result<Vec<_>>>()?;
+        let bid = line.number()?;
+        line.end()?;
+        if let TieBreak::Strongest = rules.tie_break {
+            ranks.sort_by(|a, b| b.cmp(a));
+        }
+        Ok(Self {
+            kind: rules.categorize(cards),
+            cards: ranks,
+            bid,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -134,12 +171,46 @@ mod tests {
     #[test]
     fn test_part_one() {
         let result = part_one(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(6440));
+        assert_eq!(result, Ok(6440));
     }
 
     #[test]
     fn test_part_two() {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(5905));
+        assert_eq!(result, Ok(5905));
+    }
+
+    #[test]
+    fn test_variants() {
+        let three_cards = Rules {
+            hand_size: 3,
+            categories: &[&[1, 1, 1], &[2, 1], &[3]],
+            ..JOKERS
+        };
+        let input = "AKQ 1\n22A 10\n333 100\nJ2A 1000";
+        assert_eq!(
+            run(&three_cards, input),
+            Ok(1 + 2 * 1000 + 3 * 10 + 4 * 100)
+        );
+        let error = run(&three_cards, "JJ2 1\n22 2").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 1: hand has 2 cards, expecting 3"
+        );
+        let error = part_one("32T3K 765\nT55X5 684").unwrap_err();
+        assert_eq!(error.to_string(), "line 2, column 1: unrecognized card `X`");
+        let error = part_one("32T3K x").unwrap_err();
+        assert_eq!(error.to_string(), "line 1, column 7: expecting a number");
+
+        // 2A345 loses to K4Q56 on the first card dealt, but wins on its ace
+        let input = "2A345 1\nK4Q56 2";
+        assert_eq!(part_two(input), Ok(5));
+        assert_eq!(strongest_first(input), Ok(4));
+
+        // with twos wild as well, 32T3K becomes three of a kind and ranks
+        // above the two pairs of KK677
+        let input = "32T3K 1\nKK677 2";
+        assert_eq!(wild_twos(input), Ok(4));
+        assert_eq!(part_one(input), Ok(5));
     }
 }
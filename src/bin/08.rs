@@ -1,26 +1,88 @@
 #![feature(never_type)]
-use num::integer::lcm;
-use sscanf::sscanf;
-use std::collections::HashMap;
+#![feature(let_chains)]
+use advent_of_code::graph::{Graph, NodeId};
+use advent_of_code::math::Congruence;
+use advent_of_code::parse::{self, Cursor, ParseError};
+use std::fmt::{self, Write};
 
-advent_of_code::solution!(8);
+advent_of_code::solution!(8, "check" => check);
 
-pub fn part_one(input: &str) -> Option<u64> {
-    Map::parse(input)?.count_steps(|node| node == "AAA", |node| node == "ZZZ")
+pub fn part_one(input: &str) -> Result<u64, WalkError> {
+    Map::parse(input)?
+        .count_steps(|node| node == "AAA", |node| node == "ZZZ")
+        .ok_or(WalkError::Never)
 }
 
-pub fn part_two(input: &str) -> Option<u64> {
-    Map::parse(input)?.count_steps(|node| node.ends_with('A'), |node| node.ends_with('Z'))
+pub fn part_two(input: &str) -> Result<u64, WalkError> {
+    Map::parse(input)?
+        .count_steps(|node| node.ends_with('A'), |node| node.ends_with('Z'))
+        .ok_or(WalkError::Never)
 }
 
+// Describes each ghost's cycle and whether the lcm of the first steps on a
+// `Z` node would give the right answer
+fn check(input: &str) -> Result<String, WalkError> {
+    let map = Map::parse(input)?;
+    let ghosts = map.ghosts(|node| node.ends_with('A'), |node| node.ends_with('Z'));
+    let mut result = String::new();
+    for ghost in &ghosts {
+        writeln!(
+            result,
+            "{}: cycle of {} steps from step {}, on Z at {:?} ({})",
+            map.graph.name(ghost.start),
+            ghost.period,
+            ghost.offset,
+            ghost.hits,
+            if ghost.is_clean() {
+                "clean"
+            } else {
+                "not clean"
+            },
+        )
+        .unwrap();
+    }
+    let is_clean = ghosts.iter().all(Ghost::is_clean);
+    writeln!(
+        result,
+        "lcm shortcut: {}",
+        if is_clean { "applies" } else { "doesn't apply" }
+    )
+    .unwrap();
+    Ok(result)
+}
+
+#[derive(Debug, PartialEq)]
+pub enum WalkError {
+    Parse(ParseError),
+    // no step has every walk on a destination at once
+    Never,
+}
+
+impl From<ParseError> for WalkError {
+    fn from(error: ParseError) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl fmt::Display for WalkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(error) => write!(f, "{error}"),
+            Self::Never => write!(f, "the walks never all reach a destination together"),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
 enum Direction {
     Left,
     Right,
 }
 
+// Each node's outputs are its left then its right neighbor
 struct Map<'a> {
     directions: Vec<Direction>,
-    nodes: HashMap<&'a str, (&'a str, &'a str)>,
+    graph: Graph<'a>,
 }
 
 impl<'a> Map<'a> {
@@ -29,49 +91,159 @@ impl<'a> Map<'a> {
         F: Fn(&str) -> bool,
         G: Fn(&str) -> bool,
     {
-        self.nodes
-            .keys()
-            .filter(|node| is_src(node))
-            .map(|mut node| {
-                for (i, dir) in self.directions.iter().cycle().enumerate() {
-                    let (left, right) = &self.nodes[node];
-                    node = match dir {
-                        Direction::Left => left,
-                        Direction::Right => right,
-                    };
-                    if is_dst(node) {
-                        return i as u64 + 1;
-                    }
-                }
-                // compiler doesn't know about cycle()
-                unreachable!("infinite loop w/ early return")
-            })
-            .reduce(lcm)
+        first_meeting(&self.ghosts(is_src, is_dst))
+    }
+
+    fn ghosts<F, G>(&self, is_src: F, is_dst: G) -> Vec<Ghost>
+    where
+        F: Fn(&str) -> bool,
+        G: Fn(&str) -> bool,
+    {
+        let graph = &self.graph;
+        let is_dst: Vec<bool> = graph.nodes().map(|id| is_dst(graph.name(id))).collect();
+        graph
+            .nodes()
+            .filter(|id| is_src(graph.name(*id)))
+            .map(|start| self.ghost(start, &is_dst))
+            .collect()
     }
 
-    fn parse(input: &'a str) -> Option<Self> {
-        let (first, rest) = input.split_once("\n\n")?;
+    // Follows the directions from `start` until it's back in a state (node
+    // and position in the directions) it has been in before.
+    fn ghost(&self, start: NodeId, is_dst: &[bool]) -> Ghost {
+        let len = self.directions.len();
+        let mut seen = vec![None; self.graph.len() * len];
+        let mut hits = Vec::new();
+        let mut node = start;
+        let mut step = 0;
+        loop {
+            let state = node.index() * len + step % len;
+            if let Some(offset) = seen[state] {
+                return Ghost {
+                    start,
+                    hits,
+                    offset,
+                    period: step - offset,
+                };
+            }
+            seen[state] = Some(step);
+            if step > 0 && is_dst[node.index()] {
+                hits.push(step);
+            }
+            node = self.graph.outputs(node)[self.directions[step % len] as usize];
+            step += 1;
+        }
+    }
 
-        let directions = first
-            .chars()
-            .map(|c| match c {
-                'L' => Direction::Left,
-                _ => Direction::Right,
-            })
-            .collect();
+    fn parse(input: &'a str) -> parse::Result<Self> {
+        let mut blocks = parse::blocks(input);
+        let mut first = blocks
+            .next()
+            .ok_or_else(|| Cursor::new(input).error("expecting directions"))?;
+        let mut directions = Vec::new();
+        while !first.is_empty() {
+            if first.tag("L").is_ok() {
+                directions.push(Direction::Left);
+            } else {
+                first
+                    .tag("R")
+                    .map_err(|_| first.trim_start().error("expecting `L` or `R`"))?;
+                directions.push(Direction::Right);
+            }
+        }
 
-        let nodes = rest
-            .lines()
-            .flat_map(|line| {
-                let (node, left, right) = sscanf!(line, "{} = ({}, {})", &str, &str, &str).ok()?;
-                Some((node, (left, right)))
-            })
-            .collect();
+        let mut graph = Graph::new();
+        for line in blocks.flat_map(Cursor::lines) {
+            let (node, mut rest) = parse::key_value(line, "=")?;
+            rest.tag("(")?;
+            let left = rest.until(",")?.word()?;
+            let right = rest.until(")")?.word()?;
+            rest.end()?;
+            let node = graph.node(node);
+            for neighbor in [left, right] {
+                let neighbor = graph.node(neighbor);
+                graph.add_edge(node, neighbor);
+            }
+        }
 
-        Some(Map { directions, nodes })
+        Ok(Map { directions, graph })
     }
 }
 
+// One ghost's walk: after the first `offset` steps it repeats every
+// `period` steps, and `hits` are the steps before the first repeat that end
+// on a destination.
+struct Ghost {
+    start: NodeId,
+    hits: Vec<usize>,
+    offset: usize,
+    period: usize,
+}
+
+impl Ghost {
+    fn is_at_dst(&self, step: usize) -> bool {
+        if step < self.offset {
+            return self.hits.contains(&step);
+        }
+        self.cycle_hits()
+            .any(|hit| (step - self.offset) % self.period == (hit - self.offset) % self.period)
+    }
+
+    fn cycle_hits(&self) -> impl Iterator<Item = usize> + '_ {
+        self.hits.iter().copied().filter(|hit| *hit >= self.offset)
+    }
+
+    // Whether the ghost is on a destination exactly every `n` steps, where
+    // `n` is the first step it is, as the puzzle input is built to be
+    fn is_clean(&self) -> bool {
+        let Some(&first) = self.hits.first() else {
+            return false;
+        };
+        let multiples = (first..self.offset + self.period).step_by(first);
+        // given that, the period is a multiple of `first` exactly when it's
+        // one of the hits
+        self.hits.iter().copied().eq(multiples) && self.hits.contains(&self.period)
+    }
+}
+
+// The first step on which every ghost is on a destination
+fn first_meeting(ghosts: &[Ghost]) -> Option<u64> {
+    // Until every ghost has started cycling, a meeting has to be one of the
+    // steps before the cycle of whichever ghost hasn't.
+    let before = ghosts
+        .iter()
+        .flat_map(|ghost| ghost.hits.iter().filter(|hit| **hit < ghost.offset))
+        .filter(|step| ghosts.iter().all(|ghost| ghost.is_at_dst(**step)))
+        .min();
+    if let Some(step) = before {
+        return Some(*step as u64);
+    }
+
+    // After that each ghost is on a destination at steps congruent to one
+    // of its hits in the cycle, so try every combination of those.
+    let mut solutions = vec![Congruence::new(0, 1)];
+    for ghost in ghosts {
+        let mut next = Vec::new();
+        for solution in &solutions {
+            for hit in ghost.cycle_hits() {
+                let hit = Congruence::new(hit as i128, ghost.period as i128);
+                if let Some(solution) = solution.combine(hit)
+                    && !next.contains(&solution)
+                {
+                    next.push(solution);
+                }
+            }
+        }
+        solutions = next;
+    }
+    let min = ghosts.iter().map(|ghost| ghost.offset).max()?;
+    let step = solutions
+        .iter()
+        .map(|solution| solution.at_least(min as i128))
+        .min()?;
+    step.try_into().ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -81,7 +253,7 @@ mod tests {
         let result = part_one(&advent_of_code::template::read_file_part(
             1, "examples", DAY,
         ));
-        assert_eq!(result, Some(6));
+        assert_eq!(result, Ok(6));
     }
 
     #[test]
@@ -89,6 +261,32 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file_part(
             2, "examples", DAY,
         ));
-        assert_eq!(result, Some(6));
+        assert_eq!(result, Ok(6));
+    }
+
+    #[test]
+    fn test_offset_cycles() {
+        let input = advent_of_code::template::read_file_part(3, "examples", DAY);
+        assert_eq!(part_two(&input), Ok(5));
+        assert!(check(&input)
+            .unwrap()
+            .ends_with("lcm shortcut: doesn't apply\n"));
+
+        let input = advent_of_code::template::read_file_part(2, "examples", DAY);
+        assert!(check(&input).unwrap().ends_with("lcm shortcut: applies\n"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = part_one("LRX\n\nAAA = (ZZZ, ZZZ)\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 1, column 3: expecting `L` or `R`");
+        let error = part_one("L\n\nAAA = (ZZZ, ZZZ)\nZZZ = ZZZ, ZZZ)\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 4, column 7: expecting `(`");
+        let error = part_one("L\n\nAAA = (ZZZ ZZZ)\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 3, column 8: expecting `,`");
+        assert_eq!(
+            part_one("L\n\nAAA = (BBB, BBB)\nBBB = (BBB, BBB)\n"),
+            Err(WalkError::Never)
+        );
     }
 }
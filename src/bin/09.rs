@@ -1,48 +1,23 @@
+use advent_of_code::sequence;
+
 advent_of_code::solution!(9);
 
 pub fn part_one(input: &str) -> Option<i64> {
     let report = Report::parse(input);
-    let result = report
+    report
         .histories
-        .into_iter()
-        .map(|history| extrapolate(history.into_iter()))
-        .sum();
-    Some(result)
+        .iter()
+        .map(|history| sequence::next(history))
+        .sum()
 }
 
 pub fn part_two(input: &str) -> Option<i64> {
     let report = Report::parse(input);
-    let result = report
+    report
         .histories
-        .into_iter()
-        .map(|history| extrapolate(history.into_iter().rev()))
-        .sum();
-    Some(result)
-}
-
-fn extrapolate<I>(history: I) -> i64
-where
-    I: Iterator<Item = i64>,
-{
-    let mut diffs: Vec<i64> = history.collect();
-    let mut result = diffs[diffs.len() - 1];
-    loop {
-        let mut done = true;
-        let mut diff = 0;
-        diffs = diffs
-            .windows(2)
-            .map(|pair| {
-                diff = pair[1] - pair[0];
-                done = done && diff == 0;
-                diff
-            })
-            .collect();
-        if done {
-            break;
-        }
-        result += diff;
-    }
-    result
+        .iter()
+        .map(|history| sequence::previous(history))
+        .sum()
 }
 
 #[derive(Debug)]
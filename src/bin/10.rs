@@ -1,48 +1,104 @@
-#![feature(let_chains)]
-#![feature(array_windows)]
-
+use advent_of_code::polygon::Polygon;
+use advent_of_code::template::{ANSI_BOLD, ANSI_RESET};
 use lazy_static::lazy_static;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 
-advent_of_code::solution!(10);
+advent_of_code::solution!(10, "render" => render);
 
-pub fn part_one(input: &str) -> Option<i64> {
-    Map::parse(input)
-        .loop_path()
-        .map(|path| path.len() as i64 / 2)
+pub fn part_one(input: &str) -> Result<i64, LoopError> {
+    let path = Map::parse(input).loop_path()?;
+    Ok(path.len() as i64 / 2)
 }
 
-pub fn part_two(input: &str) -> Option<i64> {
-    let map = Map::parse(input);
-    let path = map.loop_path()?;
+pub fn part_two(input: &str) -> Result<i64, LoopError> {
+    let path = Map::parse(input).loop_path()?;
+    Ok(Polygon::new(path).interior())
+}
 
-    // https://stackoverflow.com/questions/451426/how-do-i-calculate-the-area-of-a-2d-polygon
-    let area = &path[..]
-        .array_windows::<2>()
-        .map(|[(x0, y0), (x1, y1)]| x0 * y1 - y0 * x1)
-        .sum()
-        / 2i64;
+// The loop drawn with box-drawing characters, with `S` in bold, the cells it
+// encloses as a bold `I` and everything else blank
+fn render(input: &str) -> Result<String, LoopError> {
+    let rows = Map::parse(input).classify()?;
+    let lines: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| match cell {
+                    Cell::Loop('S', pipe) => format!("{ANSI_BOLD}{}{ANSI_RESET}", box_char(*pipe)),
+                    Cell::Loop(_, pipe) => box_char(*pipe).to_string(),
+                    Cell::Inside => format!("{ANSI_BOLD}I{ANSI_RESET}"),
+                    Cell::Outside => " ".to_string(),
+                })
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+    Ok(lines.join("\n"))
+}
 
-    Some(area.abs() - (path.len() as i64 / 2 - 1))
+fn box_char(pipe: char) -> char {
+    match pipe {
+        '|' => '│',
+        '-' => '─',
+        'L' => '└',
+        'J' => '┘',
+        '7' => '┐',
+        'F' => '┌',
+        _ => pipe,
+    }
 }
 
 type Point = (i64, i64);
 
+#[derive(Debug, PartialEq)]
+pub enum LoopError {
+    NoStart,
+    // how many pipes `S` does connect to
+    Start(usize),
+    // where following the pipes leads nowhere
+    Broken(Point),
+}
+
+impl fmt::Display for LoopError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoStart => write!(f, "no S in the maze"),
+            Self::Start(count) => write!(f, "S connects to {count} pipes, not 2"),
+            Self::Broken((x, y)) => write!(f, "the loop breaks at ({x}, {y})"),
+        }
+    }
+}
+
+// Where a cell is relative to the loop, with loop cells keeping what was
+// drawn there and the pipe it stands for (which differ only for `S`)
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Cell {
+    Loop(char, char),
+    Inside,
+    Outside,
+}
+
 struct Map {
-    start: Point,
+    start: Option<Point>,
     points: HashMap<Point, Tile>,
+    width: i64,
+    height: i64,
 }
 
 impl Map {
     fn parse(input: &str) -> Self {
         let mut points = HashMap::new();
-        let mut start = (0, 0);
+        let mut start = None;
+        let (mut width, mut height) = (0, 0);
         for (y, line) in input.lines().enumerate() {
             for (x, c) in line.chars().enumerate() {
                 let point = (x as i64, y as i64);
+                width = width.max(point.0 + 1);
                 match c {
                     'S' => {
-                        start = point;
+                        start = Some(point);
                         points.insert(point, Tile::Start);
                     }
                     '.' => {
@@ -53,47 +109,92 @@ impl Map {
                     }
                 }
             }
+            height += 1;
         }
 
-        Self { start, points }
+        Self {
+            start,
+            points,
+            width,
+            height,
+        }
     }
 
     fn neighbors(&self, point: &Point) -> Option<[Point; 2]> {
         let (x, y) = *point;
-        self.points.get(point).and_then(|other| match other {
-            Tile::Start => {
-                let mut neighbors = Vec::new();
-                for (dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
-                    let neighbor = (x + dx, y + dy);
-                    if let Some(others) = self.neighbors(&neighbor)
-                        && others.contains(&(x, y))
-                    {
-                        neighbors.push(neighbor);
-                    }
-                }
-                neighbors.try_into().ok()
-            }
-            Tile::Tile(c) => Some(DIFFS[c].map(|(dx, dy)| (x + dx, y + dy))),
-        })
+        match self.points.get(point)? {
+            Tile::Start => None,
+            Tile::Tile(c) => Some(DIFFS.get(c)?.map(|(dx, dy)| (x + dx, y + dy))),
+        }
+    }
+
+    // The pipes `S` connects to and the pipe it must be to do so
+    fn start_pipe(&self) -> Result<(Point, [Point; 2], char), LoopError> {
+        let start @ (x, y) = self.start.ok_or(LoopError::NoStart)?;
+        let diffs: Vec<Point> = [(0, -1), (1, 0), (0, 1), (-1, 0)]
+            .into_iter()
+            .filter(|(dx, dy)| {
+                self.neighbors(&(x + dx, y + dy))
+                    .is_some_and(|others| others.contains(&start))
+            })
+            .collect();
+        let diffs: [Point; 2] = diffs
+            .try_into()
+            .map_err(|diffs: Vec<_>| LoopError::Start(diffs.len()))?;
+        let pipe = DIFFS
+            .iter()
+            .find(|(_, pipe)| pipe.contains(&diffs[0]) && pipe.contains(&diffs[1]))
+            .map(|(c, _)| *c)
+            .ok_or(LoopError::Start(2))?;
+        Ok((start, diffs.map(|(dx, dy)| (x + dx, y + dy)), pipe))
     }
 
     // loop path includes start point on both ends
-    fn loop_path(&self) -> Option<Vec<Point>> {
-        let mut prev = self.start;
-        let mut curr = self.start;
-        let mut path = vec![curr];
-        while let Some(neighbors) = self.neighbors(&curr) {
-            if let Some(neighbor) = neighbors.iter().find(|point| **point != prev) {
-                (curr, prev) = (*neighbor, curr);
-                path.push(curr);
-            } else {
-                return None;
-            }
-            if curr == self.start {
-                break;
-            }
+    fn loop_path(&self) -> Result<Vec<Point>, LoopError> {
+        let (start, [first, _], _) = self.start_pipe()?;
+        let mut path = vec![start];
+        let (mut prev, mut curr) = (start, first);
+        while curr != start {
+            path.push(curr);
+            let next = self
+                .neighbors(&curr)
+                .filter(|neighbors| neighbors.contains(&prev))
+                .and_then(|neighbors| neighbors.into_iter().find(|point| *point != prev))
+                .ok_or(LoopError::Broken(curr))?;
+            (prev, curr) = (curr, next);
         }
-        Some(path)
+        path.push(start);
+        Ok(path)
+    }
+
+    // Scans each row left to right: crossing a loop pipe that reaches north
+    // (`|`, `L` or `J`) flips between outside and inside. Runs along the loop
+    // such as `L-7` flip once and `L-J` twice, just as the loop crosses the
+    // row or not.
+    fn classify(&self) -> Result<Vec<Vec<Cell>>, LoopError> {
+        let (_, _, start_pipe) = self.start_pipe()?;
+        let path: HashSet<Point> = self.loop_path()?.into_iter().collect();
+        let rows = (0..self.height)
+            .map(|y| {
+                let mut inside = false;
+                (0..self.width)
+                    .map(|x| {
+                        if !path.contains(&(x, y)) {
+                            return if inside { Cell::Inside } else { Cell::Outside };
+                        }
+                        let (drawn, pipe) = match self.points.get(&(x, y)) {
+                            Some(Tile::Tile(c)) => (*c, *c),
+                            _ => ('S', start_pipe),
+                        };
+                        if matches!(pipe, '|' | 'L' | 'J') {
+                            inside = !inside;
+                        }
+                        Cell::Loop(drawn, pipe)
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(rows)
     }
 }
 
@@ -123,7 +224,7 @@ mod tests {
         let result = part_one(&advent_of_code::template::read_file_part(
             1, "examples", DAY,
         ));
-        assert_eq!(result, Some(8));
+        assert_eq!(result, Ok(8));
     }
 
     #[test]
@@ -131,6 +232,38 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file_part(
             2, "examples", DAY,
         ));
-        assert_eq!(result, Some(10));
+        assert_eq!(result, Ok(10));
+    }
+
+    #[test]
+    fn test_classify() {
+        for part in [1, 2] {
+            let input = advent_of_code::template::read_file_part(part, "examples", DAY);
+            let inside = Map::parse(&input)
+                .classify()
+                .unwrap()
+                .iter()
+                .flatten()
+                .filter(|cell| **cell == Cell::Inside)
+                .count();
+            assert_eq!(Ok(inside as i64), part_two(&input));
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let input = advent_of_code::template::read_file_part(1, "examples", DAY);
+        let result = render(&input).unwrap();
+        let result = result.replace(ANSI_BOLD, "").replace(ANSI_RESET, "");
+        assert_eq!(result, "  ┌┐\n ┌┘│\n┌┘I└┐\n│┌──┘\n└┘");
+    }
+
+    #[test]
+    fn test_bad_start() {
+        assert_eq!(part_one("S-7\n|.|\nL-J"), Ok(4));
+        assert_eq!(part_one("S-7\n..|\nL-J"), Err(LoopError::Start(1)));
+        assert_eq!(part_one("-S-\n.|."), Err(LoopError::Start(3)));
+        assert_eq!(part_one("S-7\n|.|\nL-."), Err(LoopError::Broken((2, 2))));
+        assert_eq!(part_one("..."), Err(LoopError::NoStart));
     }
 }
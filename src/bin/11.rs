@@ -1,74 +1,153 @@
-#![feature(array_windows)]
-use itertools::Itertools;
+advent_of_code::solution!(11, "factors" => factors);
 
-advent_of_code::solution!(11);
+use advent_of_code::template::runner::mode_arg;
+use std::fmt::{self, Write};
+
+const FACTORS: [i128; 6] = [1, 2, 10, 100, 1_000_000, 1_000_000_000_000_000_000_000];
 
 pub fn part_one(input: &str) -> Option<usize> {
-    run(&parse(input), 1)
+    Distances::parse(input)?.expanded(2)?.try_into().ok()
 }
 
 pub fn part_two(input: &str) -> Option<usize> {
-    run(&parse(input), 1_000_000 - 1)
+    Distances::parse(input)?
+        .expanded(1_000_000)?
+        .try_into()
+        .ok()
 }
 
-fn parse(input: &str) -> Vec<Vec<char>> {
-    input.lines().map(|line| line.chars().collect()).collect()
+// The sum for each factor in the comma-separated list passed with `--arg`,
+// or for `FACTORS`, all from the one parse
+fn factors(input: &str) -> Result<String, FactorError> {
+    factors_from(input, mode_arg().as_deref())
 }
 
-fn run(grid: &[Vec<char>], diff: i64) -> Option<usize> {
-    let galaxies = &expand(grid, diff);
-    let result = galaxies
-        .iter()
-        .combinations(2)
-        .map(|pair| manhattan_distance(pair[0], pair[1]))
-        .sum();
-    Some(result)
+fn factors_from(input: &str, list: Option<&str>) -> Result<String, FactorError> {
+    let factors = match list {
+        Some(list) => parse_factors(list)?,
+        None => FACTORS.to_vec(),
+    };
+    let distances = Distances::parse(input).ok_or(FactorError::Input)?;
+    let mut table = String::new();
+    for (&factor, sum) in factors.iter().zip(distances.expand_all(&factors)) {
+        let sum = sum.ok_or(FactorError::Overflow(factor))?;
+        writeln!(table, "{factor}: {sum}").unwrap();
+    }
+    Ok(table.trim_end().to_string())
 }
 
-type Point = (i64, i64);
-
-fn expand(grid: &[Vec<char>], diff: i64) -> Vec<Point> {
-    let mut row_diff = 0i64;
-    let row_diffs: Vec<_> = grid
-        .iter()
-        .map(|row| {
-            if row.iter().all(|c| *c == '.') {
-                row_diff += diff;
+fn parse_factors(list: &str) -> Result<Vec<i128>, FactorError> {
+    list.split(',')
+        .map(|factor| {
+            let factor = factor.trim();
+            match factor.parse() {
+                Ok(value) if value >= 1 => Ok(value),
+                Ok(value) => Err(FactorError::TooSmall(value)),
+                Err(_) => Err(FactorError::NotANumber(factor.to_string())),
             }
-            row_diff
         })
-        .collect();
+        .collect()
+}
 
-    let mut col_diff = 0i64;
-    let col_diffs: Vec<_> = (0..grid[0].len())
-        .map(|col| {
-            if (0..grid.len()).all(|row| grid[row][col] == '.') {
-                col_diff += diff;
-            }
-            col_diff
-        })
-        .collect();
-
-    let row_diffs = &row_diffs;
-    let col_diffs = &col_diffs;
-    grid.iter()
-        .enumerate()
-        .flat_map(|(y, row)| {
-            row.iter()
-                .enumerate()
-                .filter(|(_, c)| **c == '#')
-                .map(move |(x, _)| {
-                    let dx = col_diffs[x];
-                    let dy = row_diffs[y];
-                    (x as i64 + dx, y as i64 + dy)
-                })
+#[derive(Debug, PartialEq)]
+enum FactorError {
+    Input,
+    NotANumber(String),
+    TooSmall(i128),
+    // a factor whose sum doesn't fit in an `i128`
+    Overflow(i128),
+}
+
+impl fmt::Display for FactorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Input => write!(f, "no galaxies to measure"),
+            Self::NotANumber(factor) => write!(f, "`{factor}` isn't a number"),
+            Self::TooSmall(factor) => write!(f, "factor {factor} is less than 1"),
+            Self::Overflow(factor) => write!(f, "the sum for factor {factor} overflows"),
+        }
+    }
+}
+
+// The sum of the distances between every pair of galaxies is linear in the
+// expansion factor: each empty line between two galaxies counts `factor`
+// times and every other line once, so it's `base + empty * (factor - 1)`.
+struct Distances {
+    base: i128,
+    empty: i128,
+}
+
+impl Distances {
+    fn parse(input: &str) -> Option<Self> {
+        let galaxies: Vec<(usize, usize)> = input
+            .lines()
+            .enumerate()
+            .flat_map(|(y, line)| line.match_indices('#').map(move |(x, _)| (x, y)))
+            .collect();
+        let width = input.lines().map(str::len).max()?;
+        let height = input.lines().count();
+
+        let (xs, ys): (Vec<_>, Vec<_>) = galaxies.into_iter().unzip();
+        let (columns, rows) = (Axis::new(xs, width), Axis::new(ys, height));
+        Some(Self {
+            base: columns.base + rows.base,
+            empty: columns.empty + rows.empty,
         })
-        .unique()
-        .collect()
+    }
+
+    // None if the sum doesn't fit in an `i128`
+    fn expanded(&self, factor: i128) -> Option<i128> {
+        self.empty.checked_mul(factor - 1)?.checked_add(self.base)
+    }
+
+    fn expand_all(&self, factors: &[i128]) -> Vec<Option<i128>> {
+        factors
+            .iter()
+            .map(|&factor| self.expanded(factor))
+            .collect()
+    }
 }
 
-fn manhattan_distance(src: &Point, dst: &Point) -> usize {
-    ((dst.0 - src.0).abs() + (dst.1 - src.1).abs()) as usize
+// Pairwise distance sums along one axis
+struct Axis {
+    base: i128,
+    empty: i128,
+}
+
+impl Axis {
+    fn new(mut lines: Vec<usize>, len: usize) -> Self {
+        let mut occupied = vec![false; len];
+        for &line in &lines {
+            occupied[line] = true;
+        }
+        // how many empty lines come before each one
+        let mut before = Vec::with_capacity(len);
+        let mut count = 0;
+        for occupied in occupied {
+            before.push(count);
+            count += i128::from(!occupied);
+        }
+
+        // both are in the same order as the lines themselves
+        lines.sort_unstable();
+        let empty = lines.iter().map(|&line| before[line]);
+        Self {
+            base: pairwise_sum(lines.iter().map(|&line| line as i128)),
+            empty: pairwise_sum(empty),
+        }
+    }
+}
+
+// The sum of `b - a` over every pair of sorted values, where each value is
+// the larger one for all those before it.
+fn pairwise_sum(sorted: impl Iterator<Item = i128>) -> i128 {
+    let mut prefix = 0;
+    let mut sum = 0;
+    for (i, value) in sorted.enumerate() {
+        sum += value * i as i128 - prefix;
+        prefix += value;
+    }
+    sum
 }
 
 #[cfg(test)]
@@ -86,4 +165,43 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(82000210));
     }
+
+    #[test]
+    fn test_expand_all() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let distances = Distances::parse(&input).unwrap();
+        assert_eq!(
+            distances.expand_all(&[2, 10, 100]),
+            vec![Some(374), Some(1030), Some(8410)]
+        );
+        // past what fits in a u64
+        let factor = 10i128.pow(20);
+        assert_eq!(distances.expanded(factor), Some(210 + 82 * factor));
+        assert_eq!(distances.expanded(i128::MAX), None);
+    }
+
+    #[test]
+    fn test_factors() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let result = factors_from(&input, Some("10, 3,100000000000000000000"));
+        assert_eq!(
+            result,
+            Ok("10: 1030\n3: 456\n100000000000000000000: 8200000000000000000210".to_string())
+        );
+        assert_eq!(factors_from(&input, None).unwrap().lines().count(), 6);
+
+        assert_eq!(
+            factors_from(&input, Some("2,x")),
+            Err(FactorError::NotANumber("x".to_string()))
+        );
+        assert_eq!(
+            factors_from(&input, Some("2,0")),
+            Err(FactorError::TooSmall(0))
+        );
+        let max = i128::MAX.to_string();
+        assert_eq!(
+            factors_from(&input, Some(&max)),
+            Err(FactorError::Overflow(i128::MAX))
+        );
+    }
 }
@@ -1,92 +1,172 @@
-advent_of_code::solution!(12);
+advent_of_code::solution!(12, "unfold" => unfold, "memo" => memo);
 
-use std::collections::HashMap;
+use advent_of_code::memo::Memo;
+use advent_of_code::template::runner::mode_arg;
+use num::{BigUint, CheckedAdd, One, Zero};
+use rayon::prelude::*;
 
 pub fn part_one(input: &str) -> Option<usize> {
-    run(input, 1)
+    arrangements(input, 1)
 }
 
 pub fn part_two(input: &str) -> Option<usize> {
-    run(input, 5)
+    arrangements(input, 5)
 }
 
-fn run(input: &str, replicate: usize) -> Option<usize> {
-    Some(
-        parse(input, replicate)
-            .map(|(springs, counts)| count_assignments(&springs, &counts))
-            .sum(),
-    )
+// Sums the arrangements of every line unfolded `factor` times, or None if
+// the total doesn't fit in `T`; `BigUint` always does.
+fn arrangements<T>(input: &str, factor: usize) -> Option<T>
+where
+    T: Clone + Zero + One + CheckedAdd + Send,
+{
+    let records: Vec<Record> = input.lines().map(Record::parse).collect::<Option<_>>()?;
+    records
+        .par_iter()
+        .map(|record| record.unfold(factor).count::<T>())
+        .try_reduce(T::zero, |a, b| a.checked_add(&b))
 }
 
-fn parse(input: &str, replicate: usize) -> impl Iterator<Item = (Vec<Spring>, Vec<u32>)> + '_ {
-    input.lines().flat_map(move |line| {
-        let (head, tail) = line.split_once(' ')?;
-        let head = vec![head; replicate].join("?");
-        let tail = vec![tail; replicate].join(",");
-        let springs: Vec<_> = head.chars().flat_map(Spring::parse).collect();
-        let counts: Vec<_> = tail.split(',').flat_map(str::parse).collect();
-        Some((springs, counts))
-    })
+fn big_arrangements(input: &str, factor: usize) -> Option<BigUint> {
+    arrangements(input, factor)
 }
 
-type Key = (usize, usize, u32);
+// The total unfolded by the factor passed with `--arg`, or part two's 5,
+// counted exactly however far it goes past a `usize`
+fn unfold(input: &str) -> Option<BigUint> {
+    let factor = mode_arg().map_or(Some(5), |arg| arg.parse().ok())?;
+    big_arrangements(input, factor)
+}
 
-fn count_assignments(springs: &[Spring], counts: &[u32]) -> usize {
-    let mut cache = HashMap::new();
-    go(&mut cache, springs, counts, None)
+// Part two counted by the recursive search, cached through `Memo`, as a
+// check on the table
+fn memo(input: &str) -> Option<usize> {
+    let records: Vec<Record> = input.lines().map(Record::parse).collect::<Option<_>>()?;
+    records.iter().try_fold(0usize, |total, record| {
+        total.checked_add(record.unfold(5).count_memo())
+    })
 }
 
-fn go(
-    cache: &mut HashMap<Key, usize>,
-    springs: &[Spring],
-    counts: &[u32],
-    run: Option<u32>,
-) -> usize {
-    if springs.is_empty() {
-        return match (run, counts) {
-            (None, []) => return 1,
-            (Some(x), [y]) if x == *y => 1,
-            _ => 0,
-        };
-    }
+struct Record {
+    springs: Vec<Spring>,
+    groups: Vec<usize>,
+}
 
-    if run.is_some() && counts.is_empty() {
-        return 0;
+impl Record {
+    fn parse(line: &str) -> Option<Self> {
+        let (springs, groups) = line.split_once(' ')?;
+        Some(Self {
+            springs: springs.chars().map(Spring::parse).collect::<Option<_>>()?,
+            groups: groups
+                .split(',')
+                .map(|group| group.parse().ok())
+                .collect::<Option<_>>()?,
+        })
     }
 
-    let key = (springs.len(), counts.len(), run.unwrap_or_default());
-    if let Some(&result) = cache.get(&key) {
-        return result;
+    // `factor` copies of the springs joined by unknowns, and of the groups
+    fn unfold(&self, factor: usize) -> Self {
+        let mut springs = Vec::with_capacity((self.springs.len() + 1) * factor);
+        for copy in 0..factor {
+            if copy > 0 {
+                springs.push(Spring::Unknown);
+            }
+            springs.extend_from_slice(&self.springs);
+        }
+        Self {
+            springs,
+            groups: self.groups.repeat(factor),
+        }
     }
 
-    let result = match (&springs[0], run) {
-        (Spring::Working, Some(x)) => {
-            if x == counts[0] {
-                go(cache, &springs[1..], &counts[1..], None)
-            } else {
-                0
+    // Goes through the groups one at a time, where `ways[i]` counts the ways
+    // to fit the groups so far in the first `i` springs with the next group
+    // free to start at spring `i`.
+    fn count<T: Clone + Zero + One + CheckedAdd>(&self) -> Option<T> {
+        let springs = &self.springs;
+        let len = springs.len();
+
+        // how many springs from each one on could all be damaged
+        let mut runs = vec![0; len + 1];
+        for i in (0..len).rev() {
+            if springs[i] != Spring::Working {
+                runs[i] = runs[i + 1] + 1;
             }
         }
-        (Spring::Working, None) => go(cache, &springs[1..], counts, None),
-        (Spring::Damaged, Some(x)) => go(cache, &springs[1..], counts, Some(x + 1)),
-        (Spring::Damaged, None) => go(cache, &springs[1..], counts, Some(1)),
-        (Spring::Unknown, Some(x)) => {
-            let mut result = go(cache, &springs[1..], counts, Some(x + 1));
-            if counts[0] == x {
-                result += go(cache, &springs[1..], &counts[1..], None);
+
+        let mut ways = vec![T::zero(); len + 1];
+        ways[0] = T::one();
+        for i in 1..=len {
+            if springs[i - 1] != Spring::Damaged {
+                ways[i] = ways[i - 1].clone();
             }
-            result
         }
-        (Spring::Unknown, None) => {
-            let start = go(cache, &springs[1..], counts, Some(1));
-            let wait = go(cache, &springs[1..], counts, None);
-            start + wait
+
+        let mut next = vec![T::zero(); len + 1];
+        for &group in &self.groups {
+            next.fill(T::zero());
+            for i in 0..=len {
+                // the spring before `i` can be working
+                if i > 0 && springs[i - 1] != Spring::Damaged {
+                    next[i] = next[i].checked_add(&next[i - 1])?;
+                }
+                // the group fits at `i`, followed by the end or a working spring
+                if ways[i].is_zero() || runs[i] < group {
+                    continue;
+                }
+                let end = i + group;
+                let after = match springs.get(end) {
+                    None => end,
+                    Some(Spring::Damaged) => continue,
+                    Some(_) => end + 1,
+                };
+                next[after] = next[after].checked_add(&ways[i])?;
+            }
+            std::mem::swap(&mut ways, &mut next);
         }
+        Some(ways[len].clone())
+    }
+
+    fn count_memo(&self) -> usize {
+        Memo::new(key, count_from).call((&self.springs, &self.groups, 0))
+    }
+}
+
+// The springs and groups left, and how many damaged springs the current
+// group has so far
+type Args<'a> = (&'a [Spring], &'a [usize], usize);
+
+fn key(&(springs, groups, run): &Args) -> (usize, usize, usize) {
+    (springs.len(), groups.len(), run)
+}
+
+fn count_from<'a>(
+    memo: &mut Memo<Args<'a>, (usize, usize, usize), usize>,
+    args: Args<'a>,
+) -> usize {
+    let (springs, groups, run) = args;
+    let Some((&spring, rest)) = springs.split_first() else {
+        return match groups {
+            [] => usize::from(run == 0),
+            [group] => usize::from(run == *group),
+            _ => 0,
+        };
     };
-    cache.insert(key, result);
-    result
+
+    let mut total = 0;
+    if spring != Spring::Damaged {
+        if run == 0 {
+            total += memo.call((rest, groups, 0));
+        } else if groups.first() == Some(&run) {
+            total += memo.call((rest, &groups[1..], 0));
+        }
+    }
+    if spring != Spring::Working && groups.first().is_some_and(|&group| run < group) {
+        total += memo.call((rest, groups, run + 1));
+    }
+    total
 }
 
+#[derive(Copy, Clone, PartialEq, Eq)]
 enum Spring {
     Unknown,
     Working,
@@ -119,4 +199,27 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(525152));
     }
+
+    #[test]
+    fn test_big_arrangements() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        assert_eq!(big_arrangements(&input, 5), Some(BigUint::from(525152u32)));
+
+        // ?###???????? 3,2,1 alone has 10 * 15^(n-1) arrangements unfolded
+        // n times, which passes u64 at n = 16
+        let line = "?###???????? 3,2,1";
+        let expected = BigUint::from(10u32) * BigUint::from(15u32).pow(19);
+        assert_eq!(arrangements::<u64>(line, 20), None);
+        assert_eq!(big_arrangements(line, 20), Some(expected));
+    }
+
+    #[test]
+    fn test_memo() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        assert_eq!(memo(&input), part_two(&input));
+        for line in input.lines() {
+            let record = Record::parse(line).unwrap();
+            assert_eq!(Some(record.count_memo()), record.count::<usize>());
+        }
+    }
 }
@@ -1,77 +1,150 @@
-advent_of_code::solution!(13);
+advent_of_code::solution!(13, "mirrors" => mirrors);
 
 pub fn part_one(input: &str) -> Option<usize> {
-    Some(run(input, 0))
+    run(input, 0)
 }
 
 pub fn part_two(input: &str) -> Option<usize> {
-    Some(run(input, 1))
+    run(input, 1)
 }
 
-fn run(input: &str, diff: usize) -> usize {
+fn run(input: &str, diff: u32) -> Option<usize> {
     input
         .split("\n\n")
-        .map(Pattern::parse)
-        .flat_map(|pattern| {
-            pattern
-                .find_reflection(diff)
-                .map(|rows| rows * 100)
-                .or_else(|| pattern.transposed().find_reflection(diff))
-        })
+        .map(|block| Some(Pattern::parse(block)?.find_reflection(diff)?.score()))
         .sum()
 }
 
-#[derive(Debug)]
+// Each pattern with part two's mirror marked the way the puzzle draws them,
+// and its smudge as `*`
+fn mirrors(input: &str) -> Option<String> {
+    let patterns = input
+        .split("\n\n")
+        .map(|block| {
+            let pattern = Pattern::parse(block)?;
+            let reflection = pattern.find_reflection(1)?;
+            Some(pattern.render(&reflection))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(patterns.join("\n\n"))
+}
+
+// Each row and column as a bitmask of its rocks, so comparing two lines is
+// a popcount of their XOR
 struct Pattern {
-    grid: Vec<Vec<char>>,
+    rows: Vec<u64>,
+    columns: Vec<u64>,
 }
 
-impl Pattern {
-    fn parse(block: &str) -> Self {
-        let grid = block.lines().map(|line| line.chars().collect()).collect();
-        Self { grid }
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Axis {
+    Rows,
+    Columns,
+}
+
+#[derive(Debug, PartialEq)]
+struct Reflection {
+    axis: Axis,
+    // how many rows or columns come before the mirror
+    before: usize,
+    // the (x, y) of the one rock or ash that's off, if there is one, taken
+    // from the side before the mirror (fixing its reflection works too)
+    smudge: Option<(usize, usize)>,
+}
+
+impl Reflection {
+    fn score(&self) -> usize {
+        match self.axis {
+            Axis::Rows => 100 * self.before,
+            Axis::Columns => self.before,
+        }
     }
+}
 
-    fn find_reflection(&self, diff: usize) -> Option<usize> {
-        let n = self.grid.len() - 1;
-        for i in 0..n {
-            let mut same = 0;
-            let mut total = 0;
-            for (lhs, rhs) in (0..=i).rev().zip(i + 1..=n) {
-                for (x, y) in self.grid[lhs].iter().zip(self.grid[rhs].iter()) {
-                    if x == y {
-                        same += 1;
-                    }
-                    total += 1;
-                }
-            }
-            if same == total - diff {
-                return Some(i + 1);
+impl Pattern {
+    fn parse(block: &str) -> Option<Self> {
+        let lines: Vec<&str> = block.lines().collect();
+        let width = lines.first()?.len();
+        if width > 64 || lines.len() > 64 || lines.iter().any(|line| line.len() != width) {
+            return None;
+        }
+        let mut rows = vec![0; lines.len()];
+        let mut columns = vec![0; width];
+        for (y, line) in lines.iter().enumerate() {
+            for (x, _) in line.match_indices('#') {
+                rows[y] |= 1 << x;
+                columns[x] |= 1 << y;
             }
         }
-        None
+        Some(Self { rows, columns })
     }
 
-    fn transposed(self) -> Self {
-        Self {
-            grid: transpose(self.grid),
+    // The mirror with exactly `diff` cells that don't match their reflection,
+    // trying rows first
+    fn find_reflection(&self, diff: u32) -> Option<Reflection> {
+        if let Some((before, smudge)) = find_mirror(&self.rows, diff) {
+            return Some(Reflection {
+                axis: Axis::Rows,
+                before,
+                smudge: smudge.map(|(y, x)| (x, y)),
+            });
         }
+        let (before, smudge) = find_mirror(&self.columns, diff)?;
+        Some(Reflection {
+            axis: Axis::Columns,
+            before,
+            smudge,
+        })
+    }
+
+    fn render(&self, reflection: &Reflection) -> String {
+        let (width, height) = (self.columns.len(), self.rows.len());
+        let marker = |i: usize, lo: char, hi: char| match i + 1 {
+            n if n == reflection.before => lo,
+            n if n == reflection.before + 1 => hi,
+            _ => ' ',
+        };
+
+        let mut lines = Vec::with_capacity(height + 1);
+        if reflection.axis == Axis::Columns {
+            lines.push((0..width).map(|x| marker(x, '>', '<')).collect::<String>());
+        }
+        for (y, row) in self.rows.iter().enumerate() {
+            let mut line: String = (0..width)
+                .map(|x| match (row >> x) & 1 {
+                    _ if reflection.smudge == Some((x, y)) => '*',
+                    1 => '#',
+                    _ => '.',
+                })
+                .collect();
+            if reflection.axis == Axis::Rows {
+                line.insert(0, marker(y, 'v', '^'));
+            }
+            lines.push(line.trim_end().to_string());
+        }
+        lines.join("\n")
     }
 }
 
-// https://stackoverflow.com/questions/64498617/how-to-transpose-a-vector-of-vectors-in-rust
-fn transpose<T>(v: Vec<Vec<T>>) -> Vec<Vec<T>> {
-    assert!(!v.is_empty());
-    let len = v[0].len();
-    let mut iters: Vec<_> = v.into_iter().map(|n| n.into_iter()).collect();
-    (0..len)
-        .map(|_| {
-            iters
-                .iter_mut()
-                .map(|n| n.next().unwrap())
-                .collect::<Vec<T>>()
-        })
-        .collect()
+// How many lines come before a mirror with exactly `diff` mismatched cells,
+// and with a single mismatch which line (the one before the mirror) and bit
+// it's in
+fn find_mirror(lines: &[u64], diff: u32) -> Option<(usize, Option<(usize, usize)>)> {
+    (1..lines.len()).find_map(|before| {
+        let pairs = (0..before).rev().zip(before..lines.len());
+        let mismatches: u32 = pairs
+            .clone()
+            .map(|(lo, hi)| (lines[lo] ^ lines[hi]).count_ones())
+            .sum();
+        if mismatches != diff {
+            return None;
+        }
+        let smudge = pairs.clone().find_map(|(lo, hi)| {
+            let xor = lines[lo] ^ lines[hi];
+            (diff == 1 && xor != 0).then(|| (lo, xor.trailing_zeros() as usize))
+        });
+        Some((before, smudge))
+    })
 }
 
 #[cfg(test)]
@@ -89,4 +162,47 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(400));
     }
+
+    #[test]
+    fn test_smudges() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let smudges: Vec<_> = input
+            .split("\n\n")
+            .map(|block| Pattern::parse(block)?.find_reflection(1))
+            .collect();
+        assert_eq!(
+            smudges,
+            vec![
+                Some(Reflection {
+                    axis: Axis::Rows,
+                    before: 3,
+                    smudge: Some((0, 0)),
+                }),
+                Some(Reflection {
+                    axis: Axis::Rows,
+                    before: 1,
+                    smudge: Some((4, 0)),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mirrors() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let result = mirrors(&input).unwrap();
+        let first: Vec<_> = result.lines().take(7).collect();
+        assert_eq!(
+            first,
+            [
+                " *.##..##.",
+                " ..#.##.#.",
+                "v##......#",
+                "^##......#",
+                " ..#.##.#.",
+                " ..##..##.",
+                " #.#.##.#.",
+            ]
+        );
+    }
 }
@@ -1,119 +1,156 @@
 use std::collections::HashMap;
-use std::iter::once;
 
 advent_of_code::solution!(14);
 
+const SPINS: usize = 1_000_000_000;
+
 pub fn part_one(input: &str) -> Option<usize> {
     let mut platform = Platform::parse(input)?;
-    platform.collapse();
-    Some(platform.score())
+    platform.tilt_north();
+    Some(platform.load())
 }
 
 pub fn part_two(input: &str) -> Option<usize> {
     let mut platform = Platform::parse(input)?;
-    let mut seen = HashMap::from([(platform.clone(), 0)]);
-    loop {
-        platform.collapse();
-        platform.rotate(); // N -> W
-
-        platform.collapse();
-        platform.rotate(); // W -> S
-
-        platform.collapse();
-        platform.rotate(); // S -> E
-
-        platform.collapse();
-        platform.rotate(); // E -> N
-
-        if let Some(start) = seen.get(&platform) {
-            let end = seen.len();
-            let cycle_len = end - start;
-            let final_step = ((1_000_000_000 - start) % cycle_len) + start;
-            return seen
-                .into_iter()
-                .find(|(_, step)| *step == final_step)
-                .map(|(platform, _)| platform.score());
+    // the load after each spin, and when each layout was first seen
+    let mut loads = vec![platform.load()];
+    let mut seen = HashMap::from([(platform.round.clone(), 0)]);
+    for spin in 1..=SPINS {
+        platform.spin();
+        loads.push(platform.load());
+        if let Some(start) = seen.insert(platform.round.clone(), spin) {
+            let cycle_len = spin - start;
+            return Some(loads[start + (SPINS - start) % cycle_len]);
         }
-
-        seen.insert(platform.clone(), seen.len());
     }
+    loads.last().copied()
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+// Each row as a bitmask with bit x set for column x, so the rocks of a
+// whole row move at once when tilting north or south
 struct Platform {
-    columns: Vec<Vec<Tile>>,
+    round: Vec<u128>,
+    cube: Vec<u128>,
+    // the runs of cells between cubes in each row, as masks
+    segments: Vec<Vec<u128>>,
 }
 
 impl Platform {
     fn parse(input: &str) -> Option<Self> {
-        let mut lines = input.lines();
-        let first = lines.next()?;
-        let mut columns: Vec<Vec<Tile>> = (0..first.len()).map(|_| Vec::new()).collect();
-        for line in once(first).chain(lines) {
-            for (i, c) in line.chars().enumerate() {
-                columns[i].push(Tile::parse(c));
+        let (mut round, mut cube) = (Vec::new(), Vec::new());
+        let mut width = None;
+        for line in input.lines() {
+            if line.len() > 128 || *width.get_or_insert(line.len()) != line.len() {
+                return None;
             }
-        }
-        Some(Self { columns })
-    }
-
-    fn collapse(&mut self) {
-        for column in self.columns.iter_mut() {
-            for mut i in 1..column.len() {
-                'inner: while i > 0 {
-                    match (&column[i], &column[i - 1]) {
-                        (Tile::Round, Tile::Empty) => column.swap(i, i - 1),
-                        _ => break 'inner,
-                    }
-                    i -= 1;
+            let (mut r, mut c) = (0, 0);
+            for (x, tile) in line.bytes().enumerate() {
+                match tile {
+                    b'O' => r |= 1 << x,
+                    b'#' => c |= 1 << x,
+                    _ => (),
                 }
             }
+            round.push(r);
+            cube.push(c);
         }
-    }
 
-    fn score(&self) -> usize {
-        self.columns
+        let full = u128::MAX >> (128 - width?);
+        let segments = cube
             .iter()
-            .flat_map(|column| {
-                column.iter().rev().enumerate().map(|(i, tile)| match tile {
-                    Tile::Round => i + 1,
-                    _ => 0,
-                })
+            .map(|&cube| {
+                let mut segments = Vec::new();
+                let mut free = full & !cube;
+                while free != 0 {
+                    // the lowest run of set bits
+                    let low = free & free.wrapping_neg();
+                    let run = free.wrapping_add(low) & !free;
+                    let segment = run.wrapping_sub(low);
+                    segments.push(segment);
+                    free &= !segment;
+                }
+                segments
             })
-            .sum()
+            .collect();
+        Some(Self {
+            round,
+            cube,
+            segments,
+        })
+    }
+
+    fn spin(&mut self) {
+        self.tilt_north();
+        self.tilt_west();
+        self.tilt_south();
+        self.tilt_east();
     }
 
-    fn rotate(&mut self) {
-        // square input, use in-place transpose
-        let n = self.columns.len();
-        assert!(n > 0);
-        let m = self.columns[0].len();
-        assert!(n == m);
-        for i in 0..n - 1 {
-            for j in i + 1..n {
-                (self.columns[i][j], self.columns[j][i]) = (self.columns[j][i], self.columns[i][j]);
+    // Rows above `y` have already settled, so the rocks that move up out of
+    // row `y` keep going until they are blocked.
+    fn tilt_north(&mut self) {
+        for y in 1..self.round.len() {
+            let mut moving = self.round[y];
+            for y in (1..=y).rev() {
+                moving &= !(self.round[y - 1] | self.cube[y - 1]);
+                if moving == 0 {
+                    break;
+                }
+                self.round[y] &= !moving;
+                self.round[y - 1] |= moving;
             }
         }
+    }
 
-        self.columns.reverse();
+    fn tilt_south(&mut self) {
+        let height = self.round.len();
+        for y in (0..height.saturating_sub(1)).rev() {
+            let mut moving = self.round[y];
+            for y in y..height - 1 {
+                moving &= !(self.round[y + 1] | self.cube[y + 1]);
+                if moving == 0 {
+                    break;
+                }
+                self.round[y] &= !moving;
+                self.round[y + 1] |= moving;
+            }
+        }
     }
-}
 
-#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
-enum Tile {
-    Empty,
-    Round,
-    Cube,
-}
+    // The rocks in each segment pile up at its low end
+    fn tilt_west(&mut self) {
+        for (round, segments) in self.round.iter_mut().zip(&self.segments) {
+            let mut tilted = 0;
+            for &segment in segments {
+                let rocks = (*round & segment).count_ones();
+                let start = segment & segment.wrapping_neg();
+                tilted |= segment & start.checked_shl(rocks).unwrap_or(0).wrapping_sub(1);
+            }
+            *round = tilted;
+        }
+    }
 
-impl Tile {
-    fn parse(input: char) -> Self {
-        match input {
-            'O' => Self::Round,
-            '#' => Self::Cube,
-            _ => Self::Empty,
+    // ... or its high end
+    fn tilt_east(&mut self) {
+        for (round, segments) in self.round.iter_mut().zip(&self.segments) {
+            let mut tilted = 0;
+            for &segment in segments {
+                let rocks = (*round & segment).count_ones();
+                let end = 128 - segment.leading_zeros();
+                tilted |= segment & !(u128::MAX.checked_shr(128 - end + rocks).unwrap_or(0));
+            }
+            *round = tilted;
         }
     }
+
+    fn load(&self) -> usize {
+        let height = self.round.len();
+        self.round
+            .iter()
+            .enumerate()
+            .map(|(y, row)| row.count_ones() as usize * (height - y))
+            .sum()
+    }
 }
 
 #[cfg(test)]
@@ -131,4 +168,11 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(64));
     }
+
+    #[test]
+    fn test_full_width() {
+        let input = format!("{}\n{}", "O".repeat(128), ".".repeat(128));
+        assert_eq!(part_one(&input), Some(256));
+        assert_eq!(part_two(&input), Some(128));
+    }
 }
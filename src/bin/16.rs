@@ -1,118 +1,390 @@
-#![feature(let_chains)]
+advent_of_code::solution!(
+    16,
+    "render" => render,
+    "render-best" => render_best,
+    "csv" => energy_csv,
+    "heatmap" => energy_heatmap,
+);
 
-advent_of_code::solution!(16);
-
-use itertools::Itertools;
+use advent_of_code::template::runner::mode_arg;
+use advent_of_code::template::{ANSI_BOLD, ANSI_RESET};
 use rayon::prelude::*;
-use std::collections::vec_deque::VecDeque;
-use std::collections::{HashMap, HashSet};
-
-//use itertools::Itertools;
+use std::fmt::{self, Write};
 
 pub fn part_one(input: &str) -> Option<usize> {
-    Some(Grid::parse(input).count_energized((0, 0), Heading::E))
+    let grid = Grid::parse(input)?;
+    Some(Beams::new(&grid).energized(0, Heading::E).len())
 }
 
 pub fn part_two(input: &str) -> Option<usize> {
-    let grid = Grid::parse(input);
-    grid.starts()
-        .into_par_iter()
-        .map(|(point, heading)| grid.count_energized(point, heading))
-        .max()
+    let grid = Grid::parse(input)?;
+    grid.energies().into_iter().map(|energy| energy.count).max()
+}
+
+// The contraption with the cells energized in bold, from the start passed
+// with `--arg` as `(x, y) heading H` or else from part one's
+fn render(input: &str) -> Result<String, RenderError> {
+    render_from(input, mode_arg().as_deref())
+}
+
+fn render_from(input: &str, start: Option<&str>) -> Result<String, RenderError> {
+    let grid = Grid::parse(input).ok_or(RenderError::Grid)?;
+    let (cell, heading) = match start {
+        Some(start) => grid.parse_start(start)?,
+        None => (0, Heading::E),
+    };
+    Ok(grid.render(&Beams::new(&grid).energized(cell, heading)))
+}
+
+// The same for the start that gives part two's answer
+fn render_best(input: &str) -> Option<String> {
+    let grid = Grid::parse(input)?;
+    let best = grid
+        .energies()
+        .into_iter()
+        .max_by_key(|energy| energy.count)?;
+    let cells = Beams::new(&grid).energized(best.cell, best.heading);
+    Some(format!("{}\n{}", grid.describe(&best), grid.render(&cells)))
 }
 
-type Point = (i32, i32);
+fn energy_csv(input: &str) -> Option<String> {
+    let grid = Grid::parse(input)?;
+    let mut csv = String::from("x,y,heading,energized");
+    for energy in grid.energies() {
+        let (x, y) = grid.point(energy.cell);
+        write!(csv, "\n{x},{y},{},{}", energy.heading, energy.count).unwrap();
+    }
+    Some(csv)
+}
+
+// Every start's count as a colour on the side of the grid it enters from
+fn energy_heatmap(input: &str) -> Option<String> {
+    const RAMP: [u8; 10] = [17, 19, 21, 27, 33, 39, 226, 220, 208, 196];
+
+    let grid = Grid::parse(input)?;
+    let energies = grid.energies();
+    let min = energies.iter().map(|energy| energy.count).min()?;
+    let best = energies.iter().max_by_key(|energy| energy.count)?;
 
+    // the grid with a border of one cell all around for the starts
+    let (width, height) = (grid.width + 2, grid.height + 2);
+    let mut colours = vec![None; width * height];
+    for energy in &energies {
+        let (x, y) = grid.point(energy.cell);
+        let (x, y) = match energy.heading {
+            Heading::S => (x + 1, 0),
+            Heading::N => (x + 1, height - 1),
+            Heading::E => (0, y + 1),
+            Heading::W => (width - 1, y + 1),
+        };
+        let step = (energy.count - min) * (RAMP.len() - 1) / (best.count - min).max(1);
+        colours[y * width + x] = Some(RAMP[step]);
+    }
+
+    let mut map = String::new();
+    for y in 0..height {
+        for x in 0..width {
+            match colours[y * width + x] {
+                Some(colour) => write!(map, "\x1b[48;5;{colour}m {ANSI_RESET}").unwrap(),
+                None if (1..width - 1).contains(&x) && (1..height - 1).contains(&y) => {
+                    write!(map, "{}", grid.tiles[(y - 1) * grid.width + x - 1]).unwrap()
+                }
+                None => map.push(' '),
+            }
+        }
+        map.push('\n');
+    }
+    write!(map, "{min} to {}, {}", best.count, grid.describe(best)).unwrap();
+    Some(map)
+}
+
+#[derive(Debug, PartialEq)]
+enum RenderError {
+    Grid,
+    // what was passed in place of `(x, y) heading H`
+    Format(String),
+    // a cell and heading that isn't on the edge heading in
+    NotAStart((usize, usize), Heading),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Grid => write!(f, "the grid isn't rectangular"),
+            Self::Format(start) => {
+                write!(
+                    f,
+                    "expecting a start like `(0, 0) heading E`, not `{start}`"
+                )
+            }
+            Self::NotAStart((x, y), heading) => write!(
+                f,
+                "({x}, {y}) heading {heading} doesn't enter the grid from its edge"
+            ),
+        }
+    }
+}
+
+// Tiles stored row by row, with cells numbered the same way
 struct Grid {
-    grid: HashMap<Point, Tile>,
-    max_x: i32,
-    max_y: i32,
+    tiles: Vec<Tile>,
+    width: usize,
+    height: usize,
 }
 
 impl Grid {
-    fn parse(input: &str) -> Self {
-        let grid: HashMap<Point, Tile> = input
-            .lines()
-            .enumerate()
-            .flat_map(|(y, line)| {
-                line.chars()
-                    .enumerate()
-                    .map(move |(x, c)| ((x as i32, y as i32), Tile::parse(c)))
+    fn parse(input: &str) -> Option<Self> {
+        let mut tiles = Vec::new();
+        let mut height = 0;
+        for line in input.lines() {
+            tiles.extend(line.chars().map(Tile::parse));
+            height += 1;
+        }
+        let width = tiles.len().checked_div(height)?;
+        (width * height == tiles.len()).then_some(Self {
+            tiles,
+            width,
+            height,
+        })
+    }
+
+    fn step(&self, cell: usize, heading: Heading) -> Option<usize> {
+        let (x, y) = (cell % self.width, cell / self.width);
+        match heading {
+            Heading::N => (y > 0).then(|| cell - self.width),
+            Heading::E => (x + 1 < self.width).then(|| cell + 1),
+            Heading::S => (y + 1 < self.height).then(|| cell + self.width),
+            Heading::W => (x > 0).then(|| cell - 1),
+        }
+    }
+
+    fn point(&self, cell: usize) -> (usize, usize) {
+        (cell % self.width, cell / self.width)
+    }
+
+    // Every cell on the edge, heading into the grid
+    fn starts(&self) -> Vec<(usize, Heading)> {
+        let (width, height) = (self.width, self.height);
+        let mut starts = Vec::new();
+        for x in 0..width {
+            starts.push((x, Heading::S));
+            starts.push(((height - 1) * width + x, Heading::N));
+        }
+        for y in 0..height {
+            starts.push((y * width, Heading::E));
+            starts.push((y * width + width - 1, Heading::W));
+        }
+        starts
+    }
+
+    fn energies(&self) -> Vec<Energy> {
+        let beams = Beams::new(self);
+        self.starts()
+            .into_par_iter()
+            .map(|(cell, heading)| Energy {
+                cell,
+                heading,
+                count: beams.energized(cell, heading).len(),
             })
-            .collect();
-        let max_x = grid.keys().map(|(x, _)| *x).max().unwrap_or_default();
-        let max_y = grid.keys().map(|(_, y)| *y).max().unwrap_or_default();
-        Self { grid, max_x, max_y }
-    }
-
-    fn count_energized(&self, start: Point, heading: Heading) -> usize {
-        let mut seen = HashSet::new();
-        let mut queue = VecDeque::from([(start, heading)]);
-        while let Some((point, heading)) = queue.pop_front() {
-            if let Some(tile) = self.grid.get(&point)
-                && !seen.contains(&(point, heading))
-            {
-                seen.insert((point, heading));
-                match tile {
-                    Tile::Empty => {
-                        queue.push_back((heading.step(point), heading));
-                    }
-                    Tile::Vertical => {
-                        let north = (Heading::N.step(point), Heading::N);
-                        let south = (Heading::S.step(point), Heading::S);
-                        match heading {
-                            Heading::N => queue.push_back(north),
-                            Heading::S => queue.push_back(south),
-                            Heading::E | Heading::W => queue.extend([north, south]),
-                        };
-                    }
-                    Tile::Horizontal => {
-                        let east = (Heading::E.step(point), Heading::E);
-                        let west = (Heading::W.step(point), Heading::W);
-                        match heading {
-                            Heading::E => queue.push_back(east),
-                            Heading::W => queue.push_back(west),
-                            Heading::N | Heading::S => queue.extend([east, west]),
-                        };
-                    }
-                    Tile::UpLeft => {
-                        match heading {
-                            Heading::N => queue.push_back((Heading::W.step(point), Heading::W)),
-                            Heading::E => queue.push_back((Heading::S.step(point), Heading::S)),
-                            Heading::S => queue.push_back((Heading::E.step(point), Heading::E)),
-                            Heading::W => queue.push_back((Heading::N.step(point), Heading::N)),
-                        };
-                    }
-                    Tile::UpRight => {
-                        match heading {
-                            Heading::N => queue.push_back((Heading::E.step(point), Heading::E)),
-                            Heading::E => queue.push_back((Heading::N.step(point), Heading::N)),
-                            Heading::S => queue.push_back((Heading::W.step(point), Heading::W)),
-                            Heading::W => queue.push_back((Heading::S.step(point), Heading::S)),
-                        };
-                    }
-                }
+            .collect()
+    }
+
+    fn describe(&self, energy: &Energy) -> String {
+        let (x, y) = self.point(energy.cell);
+        format!(
+            "best start: ({x}, {y}) heading {}, {} energized",
+            energy.heading, energy.count
+        )
+    }
+
+    // One of `starts` written the way `describe` writes it
+    fn parse_start(&self, start: &str) -> Result<(usize, Heading), RenderError> {
+        let format = || RenderError::Format(start.to_string());
+        let (point, heading) = start
+            .trim()
+            .strip_prefix('(')
+            .and_then(|rest| rest.split_once(')'))
+            .ok_or_else(format)?;
+        let (x, y) = point.split_once(',').ok_or_else(format)?;
+        let x = x.trim().parse().map_err(|_| format())?;
+        let y = y.trim().parse().map_err(|_| format())?;
+        let heading = match heading.trim().strip_prefix("heading").map(str::trim) {
+            Some("N") => Heading::N,
+            Some("E") => Heading::E,
+            Some("S") => Heading::S,
+            Some("W") => Heading::W,
+            _ => return Err(format()),
+        };
+
+        let not_a_start = RenderError::NotAStart((x, y), heading);
+        if x >= self.width || y >= self.height {
+            return Err(not_a_start);
+        }
+        let cell = y * self.width + x;
+        if !self.starts().contains(&(cell, heading)) {
+            return Err(not_a_start);
+        }
+        Ok((cell, heading))
+    }
+
+    // Energized cells in bold, with empty ones drawn as `#`
+    fn render(&self, cells: &Cells) -> String {
+        let mut picture = String::new();
+        for (cell, tile) in self.tiles.iter().enumerate() {
+            if cell > 0 && cell % self.width == 0 {
+                picture.push('\n');
             }
+            match tile {
+                _ if !cells.contains(cell) => write!(picture, "{tile}"),
+                Tile::Empty => write!(picture, "{ANSI_BOLD}#{ANSI_RESET}"),
+                _ => write!(picture, "{ANSI_BOLD}{tile}{ANSI_RESET}"),
+            }
+            .unwrap();
         }
-        seen.iter().map(|(point, _)| point).unique().count()
+        picture
     }
+}
 
-    fn starts(&self) -> Vec<(Point, Heading)> {
-        let mut starts = Vec::new();
-        for x in 0..self.max_x {
-            starts.push(((x, 0), Heading::S));
-            starts.push(((x, self.max_y), Heading::N));
+// How many cells a beam from one start energizes
+struct Energy {
+    cell: usize,
+    heading: Heading,
+    count: usize,
+}
+
+// The path of every beam leaving a splitter, worked out once so that any
+// number of starts can share them
+struct Beams<'a> {
+    grid: &'a Grid,
+    // index of the splitter on each cell
+    splitters: Vec<Option<usize>>,
+    segments: Vec<[Segment; 2]>,
+}
+
+// The cells a beam crosses up to and including the next splitter that
+// splits it, if it doesn't leave the grid or loop first
+struct Segment {
+    cells: Cells,
+    next: Option<usize>,
+}
+
+impl<'a> Beams<'a> {
+    fn new(grid: &'a Grid) -> Self {
+        let mut splitters = vec![None; grid.tiles.len()];
+        let mut count = 0;
+        for (cell, tile) in grid.tiles.iter().enumerate() {
+            if tile.splits().is_some() {
+                splitters[cell] = Some(count);
+                count += 1;
+            }
         }
 
-        for y in 0..self.max_y {
-            starts.push(((0, y), Heading::W));
-            starts.push(((self.max_x, y), Heading::E));
+        let mut beams = Self {
+            grid,
+            splitters,
+            segments: Vec::with_capacity(count),
+        };
+        for (cell, tile) in grid.tiles.iter().enumerate() {
+            if let Some(headings) = tile.splits() {
+                let segments = headings.map(|heading| {
+                    let next = grid.step(cell, heading);
+                    beams.trace(next.map(|next| (next, heading)))
+                });
+                beams.segments.push(segments);
+            }
         }
-        starts
+        beams
+    }
+
+    fn trace(&self, mut beam: Option<(usize, Heading)>) -> Segment {
+        let mut seen = Headings::new(self.grid.tiles.len());
+        let mut cells = Cells::new(self.grid.tiles.len());
+        while let Some((cell, heading)) = beam {
+            if !seen.insert(cell, heading) {
+                break;
+            }
+            cells.insert(cell);
+            let tile = self.grid.tiles[cell];
+            if tile.splits_from(heading) {
+                return Segment {
+                    cells,
+                    next: self.splitters[cell],
+                };
+            }
+            let heading = tile.deflect(heading);
+            beam = self.grid.step(cell, heading).map(|cell| (cell, heading));
+        }
+        Segment { cells, next: None }
+    }
+
+    // The cells energized by a beam entering `cell` heading `heading`
+    fn energized(&self, cell: usize, heading: Heading) -> Cells {
+        let first = self.trace(Some((cell, heading)));
+        let mut cells = first.cells;
+        let mut reached = vec![false; self.segments.len()];
+        let mut stack: Vec<usize> = first.next.into_iter().collect();
+        while let Some(splitter) = stack.pop() {
+            if reached[splitter] {
+                continue;
+            }
+            reached[splitter] = true;
+            for segment in &self.segments[splitter] {
+                cells.union_with(&segment.cells);
+                stack.extend(segment.next);
+            }
+        }
+        cells
+    }
+}
+
+// One bit per cell
+#[derive(Clone)]
+struct Cells(Vec<u64>);
+
+impl Cells {
+    fn new(len: usize) -> Self {
+        Self(vec![0; len.div_ceil(64)])
+    }
+
+    fn insert(&mut self, cell: usize) {
+        self.0[cell / 64] |= 1 << (cell % 64);
+    }
+
+    fn union_with(&mut self, other: &Self) {
+        for (word, other) in self.0.iter_mut().zip(&other.0) {
+            *word |= other;
+        }
+    }
+
+    fn contains(&self, cell: usize) -> bool {
+        self.0[cell / 64] & (1 << (cell % 64)) != 0
+    }
+
+    fn len(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+}
+
+// Four bits per cell, one for each heading a beam has crossed it in
+struct Headings(Vec<u64>);
+
+impl Headings {
+    fn new(len: usize) -> Self {
+        Self(vec![0; (len * 4).div_ceil(64)])
+    }
+
+    // Returns whether the bit wasn't already set
+    fn insert(&mut self, cell: usize, heading: Heading) -> bool {
+        let bit = cell * 4 + heading as usize;
+        let mask = 1 << (bit % 64);
+        let word = &mut self.0[bit / 64];
+        let is_new = *word & mask == 0;
+        *word |= mask;
+        is_new
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 enum Heading {
     N,
     E,
@@ -120,18 +392,22 @@ enum Heading {
     W,
 }
 
-impl Heading {
-    fn step(&self, point: Point) -> Point {
-        let (x, y) = point;
-        match self {
-            Self::N => (x, y - 1),
-            Self::E => (x + 1, y),
-            Self::S => (x, y + 1),
-            Self::W => (x - 1, y),
-        }
+impl std::fmt::Display for Heading {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::N => "N",
+                Self::E => "E",
+                Self::S => "S",
+                Self::W => "W",
+            }
+        )
     }
 }
 
+#[derive(Copy, Clone)]
 enum Tile {
     Empty,
     Vertical,
@@ -166,7 +442,35 @@ impl Tile {
             _ => Self::Empty,
         }
     }
+
+    // The headings a splitter sends beams out in
+    fn splits(&self) -> Option<[Heading; 2]> {
+        match self {
+            Self::Vertical => Some([Heading::N, Heading::S]),
+            Self::Horizontal => Some([Heading::E, Heading::W]),
+            _ => None,
+        }
+    }
+
+    fn splits_from(&self, heading: Heading) -> bool {
+        matches!(
+            (self, heading),
+            (Self::Vertical, Heading::E | Heading::W) | (Self::Horizontal, Heading::N | Heading::S)
+        )
+    }
+
+    // The heading a beam leaves in, for tiles that don't split it
+    fn deflect(&self, heading: Heading) -> Heading {
+        match (self, heading) {
+            (Self::UpLeft, Heading::N) | (Self::UpRight, Heading::S) => Heading::W,
+            (Self::UpLeft, Heading::E) | (Self::UpRight, Heading::W) => Heading::S,
+            (Self::UpLeft, Heading::S) | (Self::UpRight, Heading::N) => Heading::E,
+            (Self::UpLeft, Heading::W) | (Self::UpRight, Heading::E) => Heading::N,
+            _ => heading,
+        }
+    }
 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -182,4 +486,60 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(51));
     }
+
+    #[test]
+    fn test_render() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let result = render_from(&input, None).unwrap();
+        let result = result.replace(ANSI_BOLD, "").replace(ANSI_RESET, "");
+        let expected = [
+            r"#|###\....",
+            r"|#-.\#....",
+            r".#...|-###",
+            r".#...##.|.",
+            r".#...##...",
+            r".#...##..\",
+            r".#../#\\..",
+            r"#-#-/##|..",
+            r".|####-|.\",
+            r".#//.|.#..",
+        ];
+        assert_eq!(result, expected.join("\n"));
+    }
+
+    #[test]
+    fn test_render_start() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let result = render_from(&input, Some("(3, 0) heading S")).unwrap();
+        assert_eq!(result.matches(ANSI_BOLD).count(), 51);
+        let best = render_best(&input).unwrap();
+        assert!(best.starts_with("best start: (3, 0) heading S, 51 energized\n"));
+        assert!(best.ends_with(&result));
+
+        assert_eq!(
+            render_from(&input, Some("(3, 3) heading S")),
+            Err(RenderError::NotAStart((3, 3), Heading::S))
+        );
+        assert_eq!(
+            render_from(&input, Some("(0, 0) heading N")),
+            Err(RenderError::NotAStart((0, 0), Heading::N))
+        );
+        assert_eq!(
+            render_from(&input, Some("(10, 0) heading S")),
+            Err(RenderError::NotAStart((10, 0), Heading::S))
+        );
+        assert_eq!(
+            render_from(&input, Some("3,0,S")),
+            Err(RenderError::Format("3,0,S".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_energy_csv() {
+        let result = energy_csv(&advent_of_code::template::read_file("examples", DAY)).unwrap();
+        let mut lines = result.lines();
+        assert_eq!(lines.next(), Some("x,y,heading,energized"));
+        assert_eq!(lines.clone().count(), 40);
+        assert!(lines.any(|line| line == "3,0,S,51"));
+    }
 }
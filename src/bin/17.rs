@@ -1,17 +1,29 @@
 #![feature(let_chains)]
-advent_of_code::solution!(17);
+advent_of_code::solution!(17, "crucible" => render_crucible, "ultra" => render_ultra);
 
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
 use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
 use std::ops::Add;
 
-pub fn part_one(input: &str) -> Option<usize> {
-    Some(Graph::parse(input).heat_loss(1, 3))
+pub fn part_one(input: &str) -> Result<usize, Unreachable> {
+    Ok(Graph::parse(input).heat_loss(1, 3)?.heat_loss)
 }
 
-pub fn part_two(input: &str) -> Option<usize> {
-    Some(Graph::parse(input).heat_loss(4, 10))
+pub fn part_two(input: &str) -> Result<usize, Unreachable> {
+    Ok(Graph::parse(input).heat_loss(4, 10)?.heat_loss)
+}
+
+fn render_crucible(input: &str) -> Result<String, Unreachable> {
+    let graph = Graph::parse(input);
+    Ok(graph.render(&graph.heat_loss(1, 3)?))
+}
+
+fn render_ultra(input: &str) -> Result<String, Unreachable> {
+    let graph = Graph::parse(input);
+    Ok(graph.render(&graph.heat_loss(4, 10)?))
 }
 
 struct Graph {
@@ -48,10 +60,13 @@ impl Graph {
         Point::new(x, y)
     }
 
-    pub fn heat_loss(&self, min: usize, max: usize) -> usize {
+    // The cheapest route from the top left to the bottom right, moving at
+    // least `min` and at most `max` blocks before turning or stopping
+    pub fn heat_loss(&self, min: usize, max: usize) -> Result<Route, Unreachable> {
         let target = self.target();
 
         let mut costs: HashMap<Entry, usize> = HashMap::new();
+        let mut prev: HashMap<Entry, Entry> = HashMap::new();
         let mut queue: BinaryHeap<(Reverse<usize>, Entry)> = BinaryHeap::new();
 
         for heading in [Heading::E, Heading::S] {
@@ -62,7 +77,15 @@ impl Graph {
 
         while let Some((Reverse(cost), entry)) = queue.pop() {
             if entry.point == target && entry.steps >= min {
-                return cost;
+                let mut path = vec![entry];
+                while let Some(entry) = prev.get(&path[path.len() - 1]) {
+                    path.push(*entry);
+                }
+                path.reverse();
+                return Ok(Route {
+                    heat_loss: cost,
+                    path,
+                });
             }
 
             for heading in [entry.heading, entry.heading.left(), entry.heading.right()] {
@@ -84,14 +107,57 @@ impl Graph {
 
                     queue.push((Reverse(cost), successor));
                     costs.insert(successor, cost);
+                    prev.insert(successor, entry);
                 }
             }
         }
-        usize::MAX
+        Err(Unreachable(target))
+    }
+
+    // The grid with each block along the route showing which way it was
+    // entered
+    pub fn render(&self, route: &Route) -> String {
+        let marks: HashMap<Point, Heading> = route
+            .path
+            .iter()
+            .filter(|entry| entry.steps > 0)
+            .map(|entry| (entry.point, entry.heading))
+            .collect();
+        let target = self.target();
+        let mut result = String::new();
+        for y in 0..=target.y {
+            for x in 0..=target.x {
+                let point = Point::new(x, y);
+                if let Some(heading) = marks.get(&point) {
+                    result.push(heading.arrow());
+                } else if let Some(cost) = self.graph.get(&point) {
+                    result.push(char::from(b'0' + *cost as u8));
+                }
+            }
+            result.push('\n');
+        }
+        result
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Route {
+    heat_loss: usize,
+    // every step from the start to the target
+    path: Vec<Entry>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Unreachable(Point);
+
+impl Error for Unreachable {}
+
+impl Display for Unreachable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no route reaches ({}, {})", self.0.x, self.0.y)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 struct Point {
     x: i64,
     y: i64,
@@ -137,6 +203,15 @@ impl Heading {
         }
     }
 
+    fn arrow(&self) -> char {
+        match self {
+            Self::N => '^',
+            Self::E => '>',
+            Self::S => 'v',
+            Self::W => '<',
+        }
+    }
+
     fn left(&self) -> Heading {
         match self {
             Self::N => Self::W,
@@ -192,12 +267,40 @@ mod tests {
     #[test]
     fn test_part_one() {
         let result = part_one(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(102));
+        assert_eq!(result, Ok(102));
     }
 
     #[test]
     fn test_part_two() {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(94));
+        assert_eq!(result, Ok(94));
+    }
+
+    #[test]
+    fn test_render() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let result = render_crucible(&input).unwrap();
+        let expected = [
+            "2>>34^>>>1323",
+            "32v>>>35v5623",
+            "32552456v>>54",
+            "3446585845v52",
+            "4546657867v>6",
+            "14385987984v4",
+            "44578769877v6",
+            "36378779796v>",
+            "465496798688v",
+            "456467998645v",
+            "12246868655<v",
+            "25465488877v5",
+            "43226746555v>",
+        ];
+        assert_eq!(result.lines().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let result = part_two("1111");
+        assert_eq!(result, Err(Unreachable(Point::new(3, 0))));
     }
 }
@@ -1,16 +1,14 @@
-#![feature(array_windows)]
-use sscanf::sscanf;
-use std::iter::once;
-use std::ops::{AddAssign, Mul};
+use advent_of_code::parse::{self, Cursor};
+use advent_of_code::polygon::Polygon;
 
 advent_of_code::solution!(18);
 
-pub fn part_one(input: &str) -> Option<i64> {
-    Some(Plan::parse(Mode::Plain, input).area())
+pub fn part_one(input: &str) -> parse::Result<i128> {
+    Ok(Plan::parse(Mode::Plain, input)?.area())
 }
 
-pub fn part_two(input: &str) -> Option<i64> {
-    Some(Plan::parse(Mode::Hex, input).area())
+pub fn part_two(input: &str) -> parse::Result<i128> {
+    Ok(Plan::parse(Mode::Hex, input)?.area())
 }
 
 #[derive(Copy, Clone)]
@@ -24,32 +22,21 @@ struct Plan {
 }
 
 impl Plan {
-    fn parse(mode: Mode, input: &str) -> Self {
-        let steps = input
-            .lines()
-            .flat_map(|line| Step::parse(mode, line))
-            .collect();
-        Self { steps }
+    fn parse(mode: Mode, input: &str) -> parse::Result<Self> {
+        let steps = parse::lines(input)
+            .map(|line| Step::parse(mode, line))
+            .collect::<parse::Result<_>>()?;
+        Ok(Self { steps })
     }
 
-    fn area(&self) -> i64 {
-        let len: i64 = self.steps.iter().map(|step| step.distance).sum();
-
-        let vertices: Vec<_> = once(Point::zero())
-            .chain(self.steps.iter().scan(Point::zero(), |point, step| {
-                *point += step.heading.diff() * step.distance;
-                Some(*point)
-            }))
-            .collect();
-
-        // https://stackoverflow.com/questions/451426/how-do-i-calculate-the-area-of-a-2d-polygon
-        let area = &vertices[..]
-            .array_windows::<2>()
-            .map(|[p1, p2]| p1.x * p2.y - p1.y * p2.x)
-            .sum()
-            / 2i64;
-
-        area.abs() + len / 2 + 1
+    fn area(&self) -> i128 {
+        // widen so long hex-encoded plans can't overflow the shoelace sum
+        let polygon = Polygon::from_steps(
+            self.steps
+                .iter()
+                .map(|step| (step.heading.diff(), step.distance as i128)),
+        );
+        polygon.enclosed()
     }
 }
 
@@ -59,18 +46,30 @@ struct Step {
 }
 
 impl Step {
-    fn parse(mode: Mode, line: &str) -> Option<Self> {
-        let (heading, distance, color) = sscanf!(line, "{str} {i64} (#{str})").ok()?;
+    fn parse(mode: Mode, mut line: Cursor) -> parse::Result<Self> {
+        let at_heading = line.trim_start();
+        let heading = line.word()?;
+        let distance = line.number()?;
+        line.tag("(#")?;
+        let at_color = line;
+        let color = line.until(")")?.rest();
+        line.end()?;
+
+        if color.len() != 6 || !color.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(at_color.error(format!("expecting 6 hex digits, not `{color}`")));
+        }
+        let heading = Heading::parse(Mode::Plain, heading)
+            .ok_or_else(|| at_heading.error(format!("unrecognized heading `{heading}`")))?;
         let (heading, distance) = match mode {
-            Mode::Plain => (Heading::parse(heading)?, distance),
+            Mode::Plain => (heading, distance),
             Mode::Hex => {
-                assert!(color.len() == 6);
-                let distance = i64::from_str_radix(&color[0..5], 16).ok()?;
-                let heading = Heading::parse(&color[5..])?;
+                let distance = i64::from_str_radix(&color[0..5], 16).unwrap();
+                let heading = Heading::parse(Mode::Hex, &color[5..])
+                    .ok_or_else(|| at_color.error(format!("`{color}` ends in no heading")))?;
                 (heading, distance)
             }
         };
-        Some(Self { heading, distance })
+        Ok(Self { heading, distance })
     }
 }
 
@@ -82,57 +81,27 @@ enum Heading {
 }
 
 impl Heading {
-    fn parse(input: &str) -> Option<Self> {
-        input.chars().next().and_then(|c| match c {
-            'R' | '0' => Some(Heading::E),
-            'D' | '1' => Some(Heading::S),
-            'L' | '2' => Some(Heading::W),
-            'U' | '3' => Some(Heading::N),
+    // A letter in the plain plan, and the last hex digit in the other
+    fn parse(mode: Mode, input: &str) -> Option<Self> {
+        match (mode, input) {
+            (Mode::Plain, "R") | (Mode::Hex, "0") => Some(Heading::E),
+            (Mode::Plain, "D") | (Mode::Hex, "1") => Some(Heading::S),
+            (Mode::Plain, "L") | (Mode::Hex, "2") => Some(Heading::W),
+            (Mode::Plain, "U") | (Mode::Hex, "3") => Some(Heading::N),
             _ => None,
-        })
+        }
     }
 
-    fn diff(&self) -> Point {
+    fn diff(&self) -> (i128, i128) {
         match self {
-            Self::N => Point::new(0, -1),
-            Self::E => Point::new(1, 0),
-            Self::S => Point::new(0, 1),
-            Self::W => Point::new(-1, 0),
+            Self::N => (0, -1),
+            Self::E => (1, 0),
+            Self::S => (0, 1),
+            Self::W => (-1, 0),
         }
     }
 }
 
-#[derive(Copy, Clone)]
-struct Point {
-    x: i64,
-    y: i64,
-}
-
-impl Point {
-    fn new(x: i64, y: i64) -> Self {
-        Self { x, y }
-    }
-
-    fn zero() -> Self {
-        Self::new(0, 0)
-    }
-}
-
-impl AddAssign<Point> for Point {
-    fn add_assign(&mut self, other: Self) {
-        self.x += other.x;
-        self.y += other.y;
-    }
-}
-
-impl Mul<i64> for Point {
-    type Output = Self;
-
-    fn mul(self, scale: i64) -> Self {
-        Self::new(self.x * scale, self.y * scale)
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -140,12 +109,33 @@ mod tests {
     #[test]
     fn test_part_one() {
         let result = part_one(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(62));
+        assert_eq!(result, Ok(62));
     }
 
     #[test]
     fn test_part_two() {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(952408144115));
+        assert_eq!(result, Ok(952408144115));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = part_one("R 6 (#70c710)\nX 5 (#0dc571)\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 1: unrecognized heading `X`"
+        );
+        let error = part_one("R 6 (#70c710)\nD x (#0dc571)\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 2, column 3: expecting a number");
+        let error = part_two("R 6 (#70c71g)\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 1, column 7: expecting 6 hex digits, not `70c71g`"
+        );
+        let error = part_two("R 6 (#70c714)\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 1, column 7: `70c714` ends in no heading"
+        );
     }
 }
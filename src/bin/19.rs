@@ -1,70 +1,314 @@
-use sscanf::sscanf;
-use std::collections::{HashMap, VecDeque};
+#![feature(let_chains)]
+use advent_of_code::parse::{self, Cursor, ParseError};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Write};
 
-advent_of_code::solution!(19);
+advent_of_code::solution!(19, "boxes" => list_boxes, "simplify" => list_flows);
 
-pub fn part_one(input: &str) -> Option<usize> {
-    let (flows, parts) = input.split_once("\n\n")?;
-    let flows = flows
-        .lines()
-        .flat_map(|line| {
-            let flow = Flow::parse(line)?;
-            Some((flow.id, flow))
-        })
-        .collect();
-    let parts: Vec<_> = parts.lines().flat_map(Part::parse).collect();
-    Some(evaluate(&parts, &flows))
+pub fn part_one(input: &str) -> Result<usize, WorkflowError> {
+    let (flows, parts) = parse_input(input)?;
+    let tree = Tree::compile(&flows).ok_or(WorkflowError::Compile)?;
+    let result = parts
+        .iter()
+        .filter(|part| tree.evaluate(part))
+        .map(|part| part.rating())
+        .sum();
+    Ok(result)
+}
+
+pub fn part_two(input: &str) -> Result<usize, WorkflowError> {
+    let (flows, _) = parse_input(input)?;
+    let tree = Tree::compile(&flows).ok_or(WorkflowError::Compile)?;
+    Ok(tree.boxes().iter().map(Part::accepts).sum())
+}
+
+// Every box of accepted ratings, one per line
+fn list_boxes(input: &str) -> Result<String, WorkflowError> {
+    let (flows, _) = parse_input(input)?;
+    let tree = Tree::compile(&flows).ok_or(WorkflowError::Compile)?;
+    let mut result = String::new();
+    for part in tree.boxes() {
+        writeln!(result, "{part}").unwrap();
+    }
+    Ok(result)
+}
+
+// The workflows after simplifying, in input syntax
+fn list_flows(input: &str) -> Result<String, WorkflowError> {
+    let (mut flows, _) = parse_input(input)?;
+    simplify(&mut flows);
+    let mut ids: Vec<_> = flows.keys().copied().collect();
+    ids.sort();
+    let mut result = String::new();
+    for id in ids {
+        writeln!(result, "{}", flows[id]).unwrap();
+    }
+    Ok(result)
+}
+
+#[derive(Debug, PartialEq)]
+pub enum WorkflowError {
+    Parse(ParseError),
+    // see `Tree::compile`
+    Compile,
 }
 
-pub fn part_two(input: &str) -> Option<usize> {
-    let (flows, _) = input.split_once("\n\n")?;
-    let flows = flows
+impl From<ParseError> for WorkflowError {
+    fn from(error: ParseError) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl Display for WorkflowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse(error) => write!(f, "{error}"),
+            Self::Compile => write!(
+                f,
+                "the workflows from `in` name a missing one, loop or have no fallback rule"
+            ),
+        }
+    }
+}
+
+type Flows<'a> = HashMap<&'a str, Flow<'a>>;
+
+// The workflows, then the parts if there are any
+fn parse_input(input: &str) -> parse::Result<(Flows<'_>, Vec<Part<usize>>)> {
+    let mut blocks = parse::blocks(input);
+    let flows = blocks
+        .next()
+        .ok_or_else(|| Cursor::new(input).error("expecting workflows"))?;
+    let flows = parse_flows(flows)?;
+    let parts = match blocks.next() {
+        Some(parts) => parts
+            .lines()
+            .map(Part::parse)
+            .collect::<parse::Result<_>>()?,
+        None => Vec::new(),
+    };
+    Ok((flows, parts))
+}
+
+fn parse_flows(input: Cursor<'_>) -> parse::Result<Flows<'_>> {
+    input
         .lines()
-        .flat_map(|line| {
+        .map(|line| {
             let flow = Flow::parse(line)?;
-            Some((flow.id, flow))
+            Ok((flow.id, flow))
         })
-        .collect();
-    Some(accepts(&flows))
+        .collect()
 }
 
-fn evaluate<'a>(parts: &[Part<usize>], flows: &HashMap<&'a str, Flow<'a>>) -> usize {
-    let mut result = 0;
-    for part in parts.iter() {
-        let mut flow = &flows["in"];
-        while let Some(target) = flow.evaluate(part) {
-            match target {
-                Target::Accept => {
-                    result += part.rating();
-                    break;
-                }
-                Target::Reject => {
-                    break;
-                }
-                Target::Label(id) => {
-                    flow = &flows[id];
+// Rewrites the workflows without changing which parts they accept: rules
+// that go to the same place as the rule after them are dropped, workflows
+// left with a single unconditional rule are inlined, and workflows that
+// `in` can't reach are removed.
+fn simplify(flows: &mut Flows) {
+    loop {
+        let mut changed = false;
+        for flow in flows.values_mut() {
+            while let [.., Rule::Lt(_, _, dst) | Rule::Gt(_, _, dst), Rule::Jmp(fallback)] =
+                flow.rules[..]
+                && dst == fallback
+            {
+                flow.rules.remove(flow.rules.len() - 2);
+                changed = true;
+            }
+        }
+
+        let inlined: HashMap<_, _> = flows
+            .values()
+            .filter_map(|flow| match flow.rules[..] {
+                [Rule::Jmp(dst)] if flow.id != "in" && dst != flow.into() => Some((flow.id, dst)),
+                _ => None,
+            })
+            .collect();
+        // workflows that jump to each other in a loop are left alone
+        let inlined: HashMap<_, _> = inlined
+            .iter()
+            .filter(|(_, dst)| !matches!(dst, Target::Label(id) if inlined.contains_key(id)))
+            .map(|(id, dst)| (*id, *dst))
+            .collect();
+        for flow in flows.values_mut() {
+            for rule in flow.rules.iter_mut() {
+                if let Target::Label(id) = rule.target()
+                    && let Some(dst) = inlined.get(id)
+                {
+                    rule.set_target(*dst);
+                    changed = true;
                 }
             }
         }
+
+        if !changed {
+            break;
+        }
     }
-    result
+
+    let mut reachable = HashSet::from(["in"]);
+    let mut stack = vec!["in"];
+    while let Some(id) = stack.pop() {
+        let Some(flow) = flows.get(id) else {
+            continue;
+        };
+        for rule in &flow.rules {
+            if let Target::Label(dst) = rule.target()
+                && reachable.insert(dst)
+            {
+                stack.push(dst);
+            }
+        }
+    }
+    flows.retain(|id, _| reachable.contains(id));
+}
+
+// The workflows starting from `in`, compiled into a binary decision tree
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Tree {
+    Accept,
+    Reject,
+    // parts rated below `value` in `category` go to `lo`, the rest to `hi`
+    Split {
+        category: usize,
+        value: usize,
+        lo: Box<Tree>,
+        hi: Box<Tree>,
+    },
 }
 
-fn accepts<'a>(flows: &HashMap<&'a str, Flow<'a>>) -> usize {
-    let mut result = 0;
-    let mut queue = VecDeque::from([("in".into(), 0, Part::full())]);
-    while let Some((target, index, part)) = queue.pop_front() {
-        match target {
-            Target::Accept => {
-                result += part.accepts();
+impl Tree {
+    // Fails if a workflow is missing, has no unconditional rule at the end,
+    // or can be reached from itself.
+    fn compile(flows: &Flows) -> Option<Self> {
+        Self::build(flows, "in".into(), 0, Part::full(), &mut Vec::new())
+    }
+
+    // Splits that can't happen within `part` are skipped, and splits with
+    // the same tree on both sides are merged.
+    fn build<'a>(
+        flows: &Flows<'a>,
+        target: Target<'a>,
+        index: usize,
+        part: Part<Range>,
+        path: &mut Vec<&'a str>,
+    ) -> Option<Self> {
+        let id = match target {
+            Target::Accept => return Some(Self::Accept),
+            Target::Reject => return Some(Self::Reject),
+            Target::Label(id) => id,
+        };
+        if index == 0 {
+            if path.contains(&id) {
+                return None;
+            }
+            path.push(id);
+        }
+
+        let next = Target::Label(id);
+        let rule = flows.get(id).and_then(|flow| flow.rules.get(index));
+        let tree = match rule.copied() {
+            None => None,
+            Some(Rule::Jmp(dst)) => Self::build(flows, dst, 0, part, path),
+            Some(Rule::Lt(category, value, dst)) => Self::split(
+                flows,
+                (category, value),
+                (dst, 0),
+                (next, index + 1),
+                part,
+                path,
+            ),
+            Some(Rule::Gt(category, value, dst)) => Self::split(
+                flows,
+                (category, value + 1),
+                (next, index + 1),
+                (dst, 0),
+                part,
+                path,
+            ),
+        };
+
+        if index == 0 {
+            path.pop();
+        }
+        tree
+    }
+
+    fn split<'a>(
+        flows: &Flows<'a>,
+        (category, value): (usize, usize),
+        lo: (Target<'a>, usize),
+        hi: (Target<'a>, usize),
+        part: Part<Range>,
+        path: &mut Vec<&'a str>,
+    ) -> Option<Self> {
+        let (min, max) = part.get(category);
+        if max < value {
+            return Self::build(flows, lo.0, lo.1, part, path);
+        }
+        if min >= value {
+            return Self::build(flows, hi.0, hi.1, part, path);
+        }
+        let lo = Self::build(
+            flows,
+            lo.0,
+            lo.1,
+            part.with(category, (min, value - 1)),
+            path,
+        )?;
+        let hi = Self::build(flows, hi.0, hi.1, part.with(category, (value, max)), path)?;
+        if lo == hi {
+            return Some(lo);
+        }
+        Some(Self::Split {
+            category,
+            value,
+            lo: Box::new(lo),
+            hi: Box::new(hi),
+        })
+    }
+
+    fn evaluate(&self, part: &Part<usize>) -> bool {
+        match self {
+            Self::Accept => true,
+            Self::Reject => false,
+            Self::Split {
+                category,
+                value,
+                lo,
+                hi,
+            } => {
+                if part.get(*category) < *value {
+                    lo.evaluate(part)
+                } else {
+                    hi.evaluate(part)
+                }
             }
-            Target::Reject => continue,
-            Target::Label(id) => {
-                queue.extend(flows[id].explore(index, part));
+        }
+    }
+
+    // The disjoint boxes of ratings that are accepted
+    fn boxes(&self) -> Vec<Part<Range>> {
+        let mut result = Vec::new();
+        let mut stack = vec![(self, Part::full())];
+        while let Some((tree, part)) = stack.pop() {
+            match tree {
+                Self::Accept => result.push(part),
+                Self::Reject => (),
+                Self::Split {
+                    category,
+                    value,
+                    lo,
+                    hi,
+                } => {
+                    let (min, max) = part.get(*category);
+                    stack.push((hi, part.with(*category, (*value, max))));
+                    stack.push((lo, part.with(*category, (min, *value - 1))));
+                }
             }
         }
+        result
     }
-    result
 }
 
 struct Flow<'a> {
@@ -73,24 +317,33 @@ struct Flow<'a> {
 }
 
 impl<'a> Flow<'a> {
-    fn parse(input: &'a str) -> Option<Self> {
-        let (id, body) = &input[0..input.len() - 1].split_once('{')?;
-        let rules = body.split(',').flat_map(Rule::parse).collect();
-        Some(Self { id, rules })
-    }
-
-    fn evaluate(&self, part: &Part<usize>) -> Option<Target<'a>> {
-        self.rules.iter().find_map(|rule| rule.evaluate(part))
+    fn parse(mut line: Cursor<'a>) -> parse::Result<Self> {
+        let id = line.until("{")?.word()?;
+        let body = line.until("}")?;
+        line.end()?;
+        let rules = body
+            .split(",")
+            .map(Rule::parse)
+            .collect::<parse::Result<_>>()?;
+        Ok(Self { id, rules })
     }
+}
 
-    fn explore(&self, index: usize, part: Part<Range>) -> Vec<(Target<'a>, usize, Part<Range>)> {
-        if let Some(rule) = self.rules.get(index) {
-            return rule.explore(self.into(), index, part);
+impl Display for Flow<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{{", self.id)?;
+        for (i, rule) in self.rules.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{rule}")?;
         }
-        Vec::new()
+        write!(f, "}}")
     }
 }
 
+const CATEGORIES: [&str; 4] = ["x", "m", "a", "s"];
+
 #[derive(Copy, Clone)]
 enum Rule<'a> {
     Lt(usize, usize, Target<'a>),
@@ -99,76 +352,59 @@ enum Rule<'a> {
 }
 
 impl<'a> Rule<'a> {
-    fn parse(input: &'a str) -> Option<Self> {
-        match input.split_once(':') {
-            None => Some(Self::Jmp(Target::parse(input)?)),
-            Some((condition, target)) => {
-                let mut chars = condition.chars();
-                let category = match chars.next()? {
-                    'x' => Some(0),
-                    'm' => Some(1),
-                    'a' => Some(2),
-                    's' => Some(3),
-                    _ => None,
-                }?;
-                let operator = chars.next()?;
-                let value = str::parse(&chars.collect::<String>()).ok()?;
-                let target = Target::parse(target)?;
-                match operator {
-                    '<' => Some(Self::Lt(category, value, target)),
-                    '>' => Some(Self::Gt(category, value, target)),
-                    _ => None,
-                }
-            }
+    fn parse(mut rule: Cursor<'a>) -> parse::Result<Self> {
+        if !rule.rest().contains(':') {
+            return Ok(Self::Jmp(Target::parse(rule)?));
         }
+        let mut condition = rule.until(":")?;
+        let category = CATEGORIES
+            .iter()
+            .position(|category| condition.tag(category).is_ok())
+            .ok_or_else(|| condition.trim_start().error("expecting a category"))?;
+        let lt = condition.tag("<").is_ok();
+        if !lt {
+            condition
+                .tag(">")
+                .map_err(|_| condition.trim_start().error("expecting `<` or `>`"))?;
+        }
+        let value = condition.number()?;
+        condition.end()?;
+        let target = Target::parse(rule)?;
+        Ok(if lt {
+            Self::Lt(category, value, target)
+        } else {
+            Self::Gt(category, value, target)
+        })
     }
 
-    fn evaluate(&self, part: &Part<usize>) -> Option<Target<'a>> {
+    fn target(&self) -> Target<'a> {
         match *self {
-            Self::Gt(category, value, target) if part.get(category) > value => Some(target),
-            Self::Lt(category, value, target) if part.get(category) < value => Some(target),
-            Self::Jmp(target) => Some(target),
-            _ => None,
+            Self::Lt(_, _, target) | Self::Gt(_, _, target) | Self::Jmp(target) => target,
         }
     }
 
-    fn explore(
-        &self,
-        src: Target<'a>,
-        index: usize,
-        part: Part<Range>,
-    ) -> Vec<(Target<'a>, usize, Part<Range>)> {
+    fn set_target(&mut self, dst: Target<'a>) {
+        match self {
+            Self::Lt(_, _, target) | Self::Gt(_, _, target) | Self::Jmp(target) => *target = dst,
+        }
+    }
+}
+
+impl Display for Rule<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match *self {
-            Rule::Lt(category, value, dst) => {
-                let (lo, hi) = part.get(category);
-                if lo >= value {
-                    vec![(src, index + 1, part)]
-                } else if hi < value {
-                    vec![(dst, 0, part)]
-                } else {
-                    let lhs = part.with(category, (lo, value - 1));
-                    let rhs = part.with(category, (value, hi));
-                    vec![(dst, 0, lhs), (src, index + 1, rhs)]
-                }
+            Self::Lt(category, value, target) => {
+                write!(f, "{}<{value}:{target}", CATEGORIES[category])
             }
-            Rule::Gt(category, value, dst) => {
-                let (lo, hi) = part.get(category);
-                if hi <= value {
-                    vec![(src, index + 1, part)]
-                } else if lo > value {
-                    vec![(dst, 0, part)]
-                } else {
-                    let lhs = part.with(category, (lo, value));
-                    let rhs = part.with(category, (value + 1, hi));
-                    vec![(src, index + 1, lhs), (dst, 0, rhs)]
-                }
+            Self::Gt(category, value, target) => {
+                write!(f, "{}>{value}:{target}", CATEGORIES[category])
             }
-            Rule::Jmp(dst) => vec![(dst, 0, part)],
+            Self::Jmp(target) => write!(f, "{target}"),
         }
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq)]
 enum Target<'a> {
     Accept,
     Reject,
@@ -176,15 +412,27 @@ enum Target<'a> {
 }
 
 impl<'a> Target<'a> {
-    fn parse(input: &'a str) -> Option<Self> {
-        input.chars().next().map(|c| match c {
-            'A' => Self::Accept,
-            'R' => Self::Reject,
-            _ => Self::Label(input),
+    fn parse(mut input: Cursor<'a>) -> parse::Result<Self> {
+        let target = input.word()?;
+        input.end()?;
+        Ok(match target {
+            "A" => Self::Accept,
+            "R" => Self::Reject,
+            _ => Self::Label(target),
         })
     }
 }
 
+impl Display for Target<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Accept => write!(f, "A"),
+            Self::Reject => write!(f, "R"),
+            Self::Label(id) => write!(f, "{id}"),
+        }
+    }
+}
+
 impl<'a> From<&Flow<'a>> for Target<'a> {
     fn from(flow: &Flow<'a>) -> Self {
         flow.id.into()
@@ -203,11 +451,20 @@ struct Part<T> {
 }
 
 impl Part<usize> {
-    fn parse(input: &str) -> Option<Self> {
-        let (x, m, a, s) = sscanf!(input, "{{x={usize},m={usize},a={usize},s={usize}}}").ok()?;
-        Some(Self {
-            ratings: [x, m, a, s],
-        })
+    fn parse(mut line: Cursor) -> parse::Result<Self> {
+        line.tag("{")?;
+        let mut ratings = [0; 4];
+        for (i, category) in CATEGORIES.iter().enumerate() {
+            if i > 0 {
+                line.tag(",")?;
+            }
+            line.tag(category)?;
+            line.tag("=")?;
+            ratings[i] = line.number()?;
+        }
+        line.tag("}")?;
+        line.end()?;
+        Ok(Self { ratings })
     }
 
     fn rating(self) -> usize {
@@ -227,6 +484,18 @@ impl Part<Range> {
     }
 }
 
+impl Display for Part<Range> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (lo, hi)) in self.ratings.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}={lo}..={hi}", CATEGORIES[i])?;
+        }
+        Ok(())
+    }
+}
+
 impl<T> Part<T> {
     fn get(&self, category: usize) -> T
     where
@@ -254,12 +523,47 @@ mod tests {
     #[test]
     fn test_part_one() {
         let result = part_one(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(19114));
+        assert_eq!(result, Ok(19114));
     }
 
     #[test]
     fn test_part_two() {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(167409079868000));
+        assert_eq!(result, Ok(167409079868000));
+    }
+
+    #[test]
+    fn test_simplify() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let (mut flows, _) = parse_input(&input).unwrap();
+        let before = Tree::compile(&flows).unwrap();
+        simplify(&mut flows);
+
+        let mut ids: Vec<_> = flows.keys().copied().collect();
+        ids.sort();
+        assert_eq!(ids, ["crn", "hdj", "in", "pv", "px", "qkq", "qqz", "rfg"]);
+        assert_eq!(flows["qqz"].to_string(), "qqz{s>2770:A,m<1801:hdj,R}");
+        assert_eq!(Tree::compile(&flows), Some(before));
+    }
+
+    #[test]
+    fn test_compile_rejects_loops() {
+        let flows = parse_flows(Cursor::new("in{x<10:a,A}\na{m>5:in,R}")).unwrap();
+        assert_eq!(Tree::compile(&flows), None);
+        let flows = parse_flows(Cursor::new("in{x<10:a,A}")).unwrap();
+        assert_eq!(Tree::compile(&flows), None);
+        assert_eq!(part_two("in{x<10:a,A}"), Err(WorkflowError::Compile));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = part_one("in{x<10:A,R}\n\n{x=1,m=2,a=3,s=4}\n{x=1,m=2,s=3}").unwrap_err();
+        assert_eq!(error.to_string(), "line 4, column 10: expecting `a`");
+        let error = part_two("in{x<10:A,R}\npx{q>5:A,R}").unwrap_err();
+        assert_eq!(error.to_string(), "line 2, column 4: expecting a category");
+        let error = part_two("in{x=10:A,R}").unwrap_err();
+        assert_eq!(error.to_string(), "line 1, column 5: expecting `<` or `>`");
+        let error = part_two("in{x<10:A,R").unwrap_err();
+        assert_eq!(error.to_string(), "line 1, column 4: expecting `}`");
     }
 }
@@ -1,7 +1,14 @@
 #![feature(let_chains)]
-advent_of_code::solution!(20);
+advent_of_code::solution!(20, "dot" => dot);
 
-use std::collections::{HashMap, HashSet, VecDeque};
+use advent_of_code::graph::{Graph, NodeId};
+use advent_of_code::math::{crt, lcm_all, Congruence};
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Write};
+
+// Gives up on watching for cycles, or on pressing the button till `rx`
+// gets a low pulse, after this many presses.
+const MAX_PRESSES: usize = 1 << 20;
 
 pub fn part_one(input: &str) -> Option<usize> {
     Some(Config::parse(input)?.step_times(1000))
@@ -11,36 +18,73 @@ pub fn part_two(input: &str) -> Option<usize> {
     Config::parse(input)?.step_till("rx")
 }
 
+// The module graph in DOT, headed by comments describing the binary
+// counters found in it and the cycles feeding `rx`.
+fn dot(input: &str) -> Option<String> {
+    let mut config = Config::parse(input)?;
+    let feeder = config.graph.id("rx").and_then(|rx| config.feeder(rx));
+    let cycles = feeder.and_then(|feeder| config.cycles(feeder));
+
+    let graph = &config.graph;
+    let mut dot = String::new();
+    for counter in config.counters() {
+        writeln!(dot, "// {}", counter.describe(graph)).unwrap();
+    }
+    if let Some(feeder) = feeder {
+        match cycles {
+            Some(cycles) => {
+                for (src, cycle) in graph.inputs(feeder).iter().zip(&cycles) {
+                    let (src, feeder) = (graph.name(*src), graph.name(feeder));
+                    writeln!(dot, "// {src} -> {feeder}: {cycle}").unwrap();
+                }
+                let presses = combine(&cycles).map_or("never".into(), |n| n.to_string());
+                writeln!(dot, "// rx: first low pulse on press {presses}").unwrap();
+            }
+            None => {
+                let feeder = graph.name(feeder);
+                writeln!(dot, "// rx: inputs of {feeder} aren't periodic").unwrap();
+            }
+        }
+    }
+    dot.push_str(&graph.dot_with(|id| match &config.modules[id.index()] {
+        Some(module) => module.attributes().into(),
+        None => "shape=plaintext".into(),
+    }));
+    Some(dot)
+}
+
 #[derive(Debug, Clone)]
 struct Config<'a> {
-    modules: HashMap<&'a str, Module<'a>>,
-    inputs: HashMap<&'a str, HashSet<&'a str>>,
-    outputs: HashMap<&'a str, HashSet<&'a str>>,
+    graph: Graph<'a>,
+    // indexed by node, `None` for outputs that aren't modules, like `rx`
+    modules: Vec<Option<Module>>,
+    broadcaster: NodeId,
 }
 
 impl<'a> Config<'a> {
     fn parse(input: &'a str) -> Option<Self> {
-        let mut inputs: HashMap<_, HashSet<_>> = HashMap::new();
-        let mut outputs: HashMap<_, HashSet<_>> = HashMap::new();
-
-        let modules = input
-            .lines()
-            .flat_map(|line| {
-                let (src, dsts) = line.split_once(" -> ")?;
-                let module = Module::parse(src)?;
-                let name = module.name();
-                for dst in dsts.split(", ") {
-                    outputs.entry(name).or_default().insert(dst);
-                    inputs.entry(dst).or_default().insert(name);
-                }
-                Some((name, module))
-            })
-            .collect();
+        let mut graph = Graph::new();
+        let mut parsed = Vec::new();
+        for line in input.lines() {
+            let (src, dsts) = line.split_once(" -> ")?;
+            let (name, module) = Module::parse(src)?;
+            let src = graph.node(name);
+            for dst in dsts.split(", ") {
+                let dst = graph.node(dst);
+                graph.add_edge(src, dst);
+            }
+            parsed.push((src, module));
+        }
 
+        let mut modules = vec![None; graph.len()];
+        for (id, module) in parsed {
+            modules[id.index()] = Some(module);
+        }
+        let broadcaster = graph.id("broadcaster")?;
         Some(Self {
+            graph,
             modules,
-            inputs,
-            outputs,
+            broadcaster,
         })
     }
 
@@ -53,46 +97,142 @@ impl<'a> Config<'a> {
         stats.lo * stats.hi
     }
 
-    fn step_till(&mut self, target: &'a str) -> Option<usize> {
+    // Presses needed before `target` gets a low pulse. This tries the
+    // structure of the puzzle input first, where `target` is fed by a
+    // single conjunction whose inputs each fire periodically, and
+    // otherwise presses the button till it happens.
+    fn step_till(&mut self, target: &str) -> Option<usize> {
+        let target = self.graph.id(target)?;
+        self.feeder(target)
+            .and_then(|feeder| self.cycles(feeder))
+            .and_then(|cycles| combine(&cycles))
+            .or_else(|| self.press_till(target))
+    }
+
+    // The conjunction that is the only input to `target`, if there is one
+    fn feeder(&self, target: NodeId) -> Option<NodeId> {
+        let [feeder] = *self.graph.inputs(target) else {
+            return None;
+        };
+        let is_conjunction = matches!(
+            self.modules[feeder.index()],
+            Some(Module::Conjunction { .. })
+        );
+        is_conjunction.then_some(feeder)
+    }
+
+    // A conjunction sends a low pulse once all its inputs have sent it high
+    // ones, so this watches when each input of `feeder` does and checks that
+    // it keeps doing so at a fixed period.
+    fn cycles(&mut self, feeder: NodeId) -> Option<Vec<Cycle>> {
+        const SAMPLES: usize = 3;
         self.reset();
-        for name in &self.inputs[target] {
-            let Some(Module::Conjunction { name, inputs, .. }) = self.modules.get(name) else {
-                continue;
-            };
-            let mut stats = Stats::new_with(
-                Some(name),
-                &inputs.keys().copied().collect::<Vec<&'a str>>(),
-            );
-            loop {
-                self.step(&mut stats);
-                if stats.watch.values().all(|presses| *presses > 0) {
-                    return Some(stats.watch.values().product());
-                }
+        let inputs = self.graph.inputs(feeder).to_vec();
+        let mut stats = Stats::watching(feeder, Pulse::Hi);
+        while inputs.iter().any(|src| stats.hits(*src).len() < SAMPLES) {
+            if stats.presses == MAX_PRESSES {
+                return None;
+            }
+            self.step(&mut stats);
+        }
+        inputs
+            .iter()
+            .map(|src| Cycle::from_hits(stats.hits(*src)))
+            .collect()
+    }
+
+    fn press_till(&mut self, target: NodeId) -> Option<usize> {
+        self.reset();
+        let mut stats = Stats::watching(target, Pulse::Lo);
+        while stats.presses < MAX_PRESSES {
+            self.step(&mut stats);
+            if stats.watch.values().any(|hits| !hits.is_empty()) {
+                return Some(stats.presses);
             }
         }
         None
     }
 
+    // Chains of flip-flops hanging off the broadcaster that count presses
+    // in binary
+    fn counters(&self) -> Vec<Counter> {
+        self.graph
+            .outputs(self.broadcaster)
+            .iter()
+            .filter_map(|head| self.counter(*head))
+            .collect()
+    }
+
+    fn counter(&self, head: NodeId) -> Option<Counter> {
+        let is_flip_flop =
+            |id: NodeId| matches!(self.modules[id.index()], Some(Module::FlipFlop { .. }));
+        let is_conjunction =
+            |id: NodeId| matches!(self.modules[id.index()], Some(Module::Conjunction { .. }));
+
+        // Each bit feeds the next, and the bits that are set at the period
+        // also feed the hub.
+        let mut bits = Vec::new();
+        let mut hub = None;
+        let mut period = 0usize;
+        let mut next = Some(head);
+        while let Some(bit) = next {
+            if !is_flip_flop(bit) || bits.contains(&bit) || bits.len() == usize::BITS as usize {
+                return None;
+            }
+            next = None;
+            for &dst in self.graph.outputs(bit) {
+                if is_flip_flop(dst) && next.is_none() {
+                    next = Some(dst);
+                } else if is_conjunction(dst) && hub.is_none_or(|hub| hub == dst) {
+                    hub = Some(dst);
+                    period |= 1 << bits.len();
+                } else {
+                    return None;
+                }
+            }
+            bits.push(bit);
+        }
+
+        // Once those bits are all set the hub sends a low pulse to the
+        // lowest bit and every bit that is clear, which wraps the count
+        // around to zero.
+        let hub = hub?;
+        if self.graph.inputs(hub).len() != period.count_ones() as usize {
+            return None;
+        }
+        for (i, bit) in bits.iter().enumerate() {
+            let is_fed = self.graph.outputs(hub).contains(bit);
+            if is_fed != (i == 0 || period >> i & 1 == 0) {
+                return None;
+            }
+        }
+        Some(Counter { bits, hub, period })
+    }
+
     fn reset(&mut self) {
-        for module in self.modules.values_mut() {
-            module.reset(&self.inputs)
+        for (id, module) in self.graph.nodes().zip(self.modules.iter_mut()) {
+            if let Some(module) = module {
+                module.reset(self.graph.inputs(id));
+            }
         }
     }
 
-    fn step(&mut self, stats: &mut Stats<'a>) {
+    fn step(&mut self, stats: &mut Stats) {
         stats.press();
-        let mut queue = VecDeque::from([(Pulse::Lo, "broadcaster", "button")]);
-        while let Some((pulse, name, src)) = queue.pop_front() {
-            let Some(module) = self.modules.get_mut(name) else {
+        // the broadcaster ignores where the button's pulse comes from
+        let mut queue = VecDeque::from([(Pulse::Lo, self.broadcaster, self.broadcaster)]);
+        while let Some((pulse, id, src)) = queue.pop_front() {
+            stats.receive(id, src, pulse);
+            let Some(module) = &mut self.modules[id.index()] else {
                 continue;
             };
 
-            let Some((pulse, name)) = module.latch(src, pulse, stats) else {
+            let Some(pulse) = module.latch(src, pulse) else {
                 continue;
             };
 
-            for dst in self.outputs[name].iter() {
-                queue.push_back((pulse, dst, name));
+            for &dst in self.graph.outputs(id) {
+                queue.push_back((pulse, dst, id));
                 stats.inc(pulse);
             }
         }
@@ -100,89 +240,72 @@ impl<'a> Config<'a> {
 }
 
 #[derive(Debug, Clone)]
-enum Module<'a> {
-    Broadcaster {
-        name: &'a str,
-    },
-    FlipFlop {
-        name: &'a str,
-        on: bool,
-    },
-    Conjunction {
-        name: &'a str,
-        inputs: HashMap<&'a str, Pulse>,
-    },
-}
-
-impl<'a> Module<'a> {
-    fn parse(input: &'a str) -> Option<Self> {
+enum Module {
+    Broadcaster,
+    FlipFlop { on: bool },
+    Conjunction { inputs: HashMap<NodeId, Pulse> },
+}
+
+impl Module {
+    fn parse(input: &str) -> Option<(&str, Self)> {
         match input {
-            "broadcaster" => Some(Self::Broadcaster { name: input }),
-            _ if input.starts_with('%') => Some(Self::FlipFlop {
-                name: &input[1..],
-                on: false,
-            }),
-            _ if input.starts_with('&') => Some(Self::Conjunction {
-                name: &input[1..],
-                inputs: HashMap::new(),
-            }),
+            "broadcaster" => Some((input, Self::Broadcaster)),
+            _ if input.starts_with('%') => Some((&input[1..], Self::FlipFlop { on: false })),
+            _ if input.starts_with('&') => Some((
+                &input[1..],
+                Self::Conjunction {
+                    inputs: HashMap::new(),
+                },
+            )),
             _ => None,
         }
     }
 
-    fn name(&self) -> &'a str {
+    fn reset(&mut self, srcs: &[NodeId]) {
         match self {
-            Self::Broadcaster { name } => name,
-            Self::FlipFlop { name, .. } => name,
-            Self::Conjunction { name, .. } => name,
-        }
-    }
-
-    fn reset(&mut self, all_inputs: &HashMap<&'a str, HashSet<&'a str>>) {
-        match self {
-            Self::Broadcaster { .. } => (),
-            Self::FlipFlop { on, .. } => *on = false,
-            Self::Conjunction { name, inputs } => {
+            Self::Broadcaster => (),
+            Self::FlipFlop { on } => *on = false,
+            Self::Conjunction { inputs } => {
                 inputs.clear();
-                if let Some(srcs) = all_inputs.get(name) {
-                    for src in srcs.iter() {
-                        inputs.insert(src, Pulse::Lo);
-                    }
+                for src in srcs {
+                    inputs.insert(*src, Pulse::Lo);
                 }
             }
         }
     }
 
-    fn latch(
-        &mut self,
-        src: &'a str,
-        pulse: Pulse,
-        stats: &mut Stats<'a>,
-    ) -> Option<(Pulse, &'a str)> {
+    fn latch(&mut self, src: NodeId, pulse: Pulse) -> Option<Pulse> {
         match self {
-            Module::Broadcaster { name } => Some((pulse, name)),
-            Module::FlipFlop { name, on } => {
+            Module::Broadcaster => Some(pulse),
+            Module::FlipFlop { on } => {
                 let Pulse::Lo = pulse else {
                     return None;
                 };
 
                 *on = !*on;
                 let pulse = if *on { Pulse::Hi } else { Pulse::Lo };
-                Some((pulse, name))
+                Some(pulse)
             }
-            Module::Conjunction { name, inputs } => {
+            Module::Conjunction { inputs } => {
                 inputs.insert(src, pulse);
                 let pulse = if inputs.values().all(|pulse| *pulse == Pulse::Hi) {
                     Pulse::Lo
                 } else {
                     Pulse::Hi
                 };
-
-                stats.watch(name, inputs);
-                Some((pulse, name))
+                Some(pulse)
             }
         }
     }
+
+    // DOT attributes telling the kinds of module apart
+    fn attributes(&self) -> &'static str {
+        match self {
+            Self::Broadcaster => "shape=doublecircle",
+            Self::FlipFlop { .. } => "shape=box",
+            Self::Conjunction { .. } => "shape=diamond",
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -191,26 +314,31 @@ enum Pulse {
     Hi,
 }
 
-struct Stats<'a> {
+struct Stats {
     presses: usize,
     lo: usize,
     hi: usize,
-    sink: Option<&'a str>,
-    watch: HashMap<&'a str, usize>,
+    // pulses of one kind arriving at one node, and the presses in which
+    // each source sent them
+    sink: Option<(NodeId, Pulse)>,
+    watch: HashMap<NodeId, Vec<usize>>,
 }
 
-impl<'a> Stats<'a> {
+impl Stats {
     fn new() -> Self {
-        Self::new_with(None, &[])
-    }
-
-    fn new_with(sink: Option<&'a str>, watch: &[&'a str]) -> Self {
         Self {
             presses: 0,
             lo: 0,
             hi: 0,
-            sink,
-            watch: watch.iter().map(|name| (*name, 0)).collect(),
+            sink: None,
+            watch: HashMap::new(),
+        }
+    }
+
+    fn watching(sink: NodeId, pulse: Pulse) -> Self {
+        Self {
+            sink: Some((sink, pulse)),
+            ..Self::new()
         }
     }
 
@@ -226,15 +354,87 @@ impl<'a> Stats<'a> {
         self.presses += 1;
     }
 
-    fn watch(&mut self, name: &'a str, inputs: &HashMap<&'a str, Pulse>) {
-        if self.sink == Some(name) {
-            for (input, pulse) in inputs.iter() {
-                if *pulse == Pulse::Hi && self.watch[input] == 0 {
-                    self.watch.insert(input, self.presses);
-                }
+    fn receive(&mut self, dst: NodeId, src: NodeId, pulse: Pulse) {
+        if self.sink == Some((dst, pulse)) {
+            let hits = self.watch.entry(src).or_default();
+            if hits.last() != Some(&self.presses) {
+                hits.push(self.presses);
             }
         }
     }
+
+    fn hits(&self, src: NodeId) -> &[usize] {
+        self.watch.get(&src).map_or(&[], Vec::as_slice)
+    }
+}
+
+// Presses `offset + period * k` for every k >= 0
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Cycle {
+    offset: usize,
+    period: usize,
+}
+
+impl Cycle {
+    fn from_hits(hits: &[usize]) -> Option<Self> {
+        let [offset, second, ..] = *hits else {
+            return None;
+        };
+        let period = second - offset;
+        let is_periodic = hits.windows(2).all(|pair| pair[1] - pair[0] == period);
+        is_periodic.then_some(Self { offset, period })
+    }
+
+    // Fires on multiples of the period, as the puzzle input's counters do
+    fn is_clean(&self) -> bool {
+        self.offset == self.period
+    }
+}
+
+impl Display for Cycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_clean() {
+            write!(f, "every {} presses", self.period)
+        } else {
+            write!(f, "press {} then every {}", self.offset, self.period)
+        }
+    }
+}
+
+// The first press on which every cycle fires. That's the lcm of the periods
+// when all the cycles are clean, and takes the Chinese Remainder Theorem
+// otherwise.
+fn combine(cycles: &[Cycle]) -> Option<usize> {
+    if cycles.iter().all(Cycle::is_clean) {
+        return lcm_all(cycles.iter().map(|cycle| cycle.period));
+    }
+    let congruences = cycles
+        .iter()
+        .map(|cycle| Congruence::new(cycle.offset as i128, cycle.period as i128));
+    let first = cycles.iter().map(|cycle| cycle.offset).max()?;
+    crt(congruences)?.at_least(first as i128).try_into().ok()
+}
+
+// Flip-flops counting presses from the lowest bit up, and the conjunction
+// that resets them every `period` presses
+struct Counter {
+    bits: Vec<NodeId>,
+    hub: NodeId,
+    period: usize,
+}
+
+impl Counter {
+    fn describe(&self, graph: &Graph) -> String {
+        format!(
+            "counter {}..{}: {} bits, hub {}, period {} ({:b})",
+            graph.name(self.bits[0]),
+            graph.name(self.bits[self.bits.len() - 1]),
+            self.bits.len(),
+            graph.name(self.hub),
+            self.period,
+            self.period,
+        )
+    }
 }
 
 #[cfg(test)]
@@ -243,7 +443,44 @@ mod tests {
 
     #[test]
     fn test_part_one() {
-        let result = part_one(&advent_of_code::template::read_file("examples", DAY));
+        let result = part_one(&advent_of_code::template::read_file_part(
+            1, "examples", DAY,
+        ));
         assert_eq!(result, Some(32000000));
     }
+
+    #[test]
+    fn test_part_two() {
+        let result = part_two(&advent_of_code::template::read_file_part(
+            2, "examples", DAY,
+        ));
+        assert_eq!(result, Some(35));
+    }
+
+    #[test]
+    fn test_counters() {
+        let input = advent_of_code::template::read_file_part(2, "examples", DAY);
+        let mut config = Config::parse(&input).unwrap();
+        let periods: Vec<_> = config.counters().iter().map(|c| c.period).collect();
+        assert_eq!(periods, [5, 7]);
+
+        let rx = config.graph.id("rx").unwrap();
+        assert_eq!(config.press_till(rx), Some(35));
+    }
+
+    #[test]
+    fn test_combine() {
+        let clean = [
+            Cycle::from_hits(&[4, 8, 12]).unwrap(),
+            Cycle::from_hits(&[6, 12, 18]).unwrap(),
+        ];
+        assert_eq!(combine(&clean), Some(12));
+
+        let offset = [
+            Cycle::from_hits(&[2, 5, 8]).unwrap(),
+            Cycle::from_hits(&[1, 5, 9]).unwrap(),
+        ];
+        assert_eq!(combine(&offset), Some(5));
+        assert_eq!(Cycle::from_hits(&[3, 5, 8]), None);
+    }
 }
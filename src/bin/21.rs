@@ -1,144 +1,278 @@
-#![feature(let_chains)]
-use std::collections::{HashMap, HashSet, VecDeque};
-
 advent_of_code::solution!(21);
 
+use std::collections::{HashMap, VecDeque};
+
+// Gives up if the distances haven't settled into a repeating pattern this
+// many tiles out from the start.
+const MAX_RADIUS: usize = 16;
+
 pub fn part_one(input: &str) -> Option<usize> {
-    let max_steps = if cfg!(test) { 6 } else { 64 };
-    let map = Map::parse(input);
-    Some(map.count_reachable_plots(max_steps))
+    Map::parse(input)?.count_reachable_plots(64)
 }
 
 pub fn part_two(input: &str) -> Option<usize> {
-    let map = Map::parse(input);
-    let max_steps = 26501365;
-    let extra = max_steps % map.size;
-
-    let mut ys = [0; 3];
-    for n in 0..3 {
-        let steps = n * map.size + extra;
-        ys[n as usize] = map.count_reachable_plots(steps as usize) as i64;
-    }
-
-    let x = max_steps / map.size;
-    Some(solve(ys, x) as usize)
+    Map::parse(input)?.count_reachable_plots(26501365)
 }
 
+struct Map {
+    plots: Vec<bool>,
+    width: usize,
+    height: usize,
+    start: (usize, usize),
+}
 
-// Find quadratic coefficients given a 3-element sequence and substitute in x
-fn solve(ys: [i64; 3], x: i64) -> i64 {
-    // First difference
-    let dy1 = ys[1] - ys[0];
-    let dy2 = ys[2] - ys[1];
-
-    // Second difference
-    let ddy = dy2 - dy1;
-
-    // 2a = second difference
-    let a = ddy / 2;
+impl Map {
+    fn parse(input: &str) -> Option<Self> {
+        let mut plots = Vec::new();
+        let mut start = None;
+        let mut height = 0;
+        for (y, line) in input.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                match c {
+                    'S' if start.is_none() => start = Some((x, y)),
+                    '.' | '#' => (),
+                    _ => return None,
+                }
+                plots.push(c != '#');
+            }
+            height += 1;
+        }
+        if height == 0 || plots.len() % height != 0 {
+            return None;
+        }
+        Some(Self {
+            width: plots.len() / height,
+            plots,
+            height,
+            start: start?,
+        })
+    }
 
-    // 3a + b = ys₂ - ys₁
-    let b = dy2 - 3 * a;
+    // Counts the plots on the infinitely repeating map that can be reached
+    // in exactly `steps` steps, i.e. those at most that far away and with
+    // the same parity.
+    //
+    // Far enough from the start, walking one more tile in any direction
+    // adds the same number of steps to every plot. The tiles within that
+    // radius are counted plot by plot, and the ones beyond it from the
+    // closest tile inside.
+    fn count_reachable_plots(&self, steps: usize) -> Option<usize> {
+        (1..=MAX_RADIUS).find_map(|radius| Some(Tiling::fit(self, radius)?.count(steps)))
+    }
+}
 
-    // first term of sequence
-    let c = ys[0];
+type Tile = (i64, i64);
 
-    return a * x.pow(2) + b * x + c;
+// Distances from the start to every plot within `radius` tiles of the start
+// tile, as a square of tiles laid out row by row.
+struct Distances {
+    radius: usize,
+    width: usize,
+    height: usize,
+    steps: Vec<Option<usize>>,
 }
 
-type Point = (i64, i64);
+impl Distances {
+    fn new(map: &Map, radius: usize) -> Self {
+        let tiles = 2 * radius + 1;
+        let (width, height) = (tiles * map.width, tiles * map.height);
+        let mut steps = vec![None; width * height];
 
-struct Map {
-    grid: HashMap<Point, Tile>,
-    size: i64,
-}
+        let start = (
+            radius * map.width + map.start.0,
+            radius * map.height + map.start.1,
+        );
+        steps[start.1 * width + start.0] = Some(0);
+        let mut queue = VecDeque::from([(start, 0)]);
+        while let Some(((x, y), step)) = queue.pop_front() {
+            let neighbors = [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ];
+            for (x, y) in neighbors {
+                if x >= width || y >= height || steps[y * width + x].is_some() {
+                    continue;
+                }
+                if map.plots[(y % map.height) * map.width + x % map.width] {
+                    steps[y * width + x] = Some(step + 1);
+                    queue.push_back(((x, y), step + 1));
+                }
+            }
+        }
 
-impl Map {
-    fn parse(input: &str) -> Self {
-        let grid: HashMap<Point, Tile> = input
-            .lines()
-            .enumerate()
-            .flat_map(|(y, line)| {
-                line.chars().enumerate().flat_map(move |(x, c)| {
-                    let tile = Tile::parse(c)?;
-                    Some(((x as i64, y as i64), tile))
-                })
-            })
-            .collect();
-        let max_x = grid.keys().map(|(x, _)| *x).max().unwrap_or_default();
-        let max_y = grid.keys().map(|(_, y)| *y).max().unwrap_or_default();
-        assert!(max_x == max_y);
         Self {
-            grid,
-            size: max_x + 1,
+            radius,
+            width: map.width,
+            height: map.height,
+            steps,
         }
     }
 
-    fn start(&self) -> Point {
-        self.grid
-            .iter()
-            .find_map(|(point, tile)| {
-                if let Tile::Start = tile {
-                    Some(*point)
-                } else {
-                    None
-                }
+    // Distances to the cells of a tile, relative to the start tile
+    fn tile(&self, (tx, ty): Tile) -> Vec<Option<usize>> {
+        let radius = self.radius as i64;
+        let x0 = (tx + radius) as usize * self.width;
+        let y0 = (ty + radius) as usize * self.height;
+        let row = (2 * self.radius + 1) * self.width;
+        (0..self.height)
+            .flat_map(|y| {
+                let offset = (y0 + y) * row + x0;
+                self.steps[offset..offset + self.width].iter().copied()
             })
-            .expect("No start point?")
+            .collect()
     }
+}
 
-    fn count_reachable_plots(&self, max_steps: usize) -> usize {
-        let start = self.start();
-        let parity = max_steps % 2;
-        let mut seen = HashSet::new();
-        let mut queue = VecDeque::from([(start, 0)]);
-        let mut count = 0;
+// Distances on the whole infinite map: the tiles within `radius` of the
+// start tile as they are, and the ones beyond it as the closest tile inside
+// plus a fixed cost for each tile further out in each direction.
+struct Tiling {
+    radius: i64,
+    tiles: HashMap<Tile, Vec<Option<usize>>>,
+    // extra steps per tile, indexed by `Self::direction`
+    costs: [usize; 4],
+}
 
-        while let Some((point @ (x, y), step)) = queue.pop_front()
-            && step <= max_steps
-        {
-            let grid_point = (x.rem_euclid(self.size), y.rem_euclid(self.size));
-            let Some(tile) = self.grid.get(&grid_point) else {
-                continue;
-            };
+impl Tiling {
+    // Checks the model against two more rings of tiles, with one more ring
+    // around those so that their distances aren't cut short.
+    fn fit(map: &Map, radius: usize) -> Option<Self> {
+        let distances = Distances::new(map, radius + 3);
+        let r = radius as i64;
 
-            if *tile == Tile::Rock {
-                continue;
-            };
+        let mut costs = [0; 4];
+        for (i, (dx, dy)) in [(1, 0), (-1, 0), (0, 1), (0, -1)].into_iter().enumerate() {
+            let inside = distances.tile((dx * r, dy * r));
+            let outside = distances.tile((dx * (r + 1), dy * (r + 1)));
+            costs[i] = inside
+                .iter()
+                .zip(&outside)
+                .find_map(|(inside, outside)| outside.as_ref()?.checked_sub(*inside.as_ref()?))
+                .unwrap_or_default();
+        }
 
-            if seen.contains(&point) {
-                continue;
-            }
-            seen.insert(point);
+        let tiles = (-r..=r)
+            .flat_map(|ty| (-r..=r).map(move |tx| (tx, ty)))
+            .map(|tile| (tile, distances.tile(tile)))
+            .collect();
+        let tiling = Self {
+            radius: r,
+            tiles,
+            costs,
+        };
 
-            if step % 2 == parity {
-                count += 1;
+        let outer = r + 2;
+        for ty in -outer..=outer {
+            for tx in -outer..=outer {
+                if tx.abs() <= r && ty.abs() <= r {
+                    continue;
+                }
+                if distances.tile((tx, ty)) != tiling.predict((tx, ty)) {
+                    return None;
+                }
             }
+        }
+        Some(tiling)
+    }
+
+    fn direction(delta: i64, positive: usize, negative: usize) -> usize {
+        if delta > 0 {
+            positive
+        } else {
+            negative
+        }
+    }
+
+    fn predict(&self, (tx, ty): Tile) -> Vec<Option<usize>> {
+        let r = self.radius;
+        let base = &self.tiles[&(tx.clamp(-r, r), ty.clamp(-r, r))];
+        let extra_x = (tx.abs() - r).max(0) as usize * self.costs[Self::direction(tx, 0, 1)];
+        let extra_y = (ty.abs() - r).max(0) as usize * self.costs[Self::direction(ty, 2, 3)];
+        base.iter()
+            .map(|steps| steps.map(|steps| steps + extra_x + extra_y))
+            .collect()
+    }
+
+    fn count(&self, steps: usize) -> usize {
+        let r = self.radius;
+        let mut count = 0;
+        for (&(tx, ty), tile) in &self.tiles {
+            let cost_x = self.costs[Self::direction(tx, 0, 1)];
+            let cost_y = self.costs[Self::direction(ty, 2, 3)];
+            let (edge_x, edge_y) = (tx.abs() == r, ty.abs() == r);
 
-            for (dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
-                queue.push_back(((x + dx, y + dy), step + 1));
+            // Many plots in a tile share a distance, so each distinct one is
+            // only worked out once.
+            let mut distances: HashMap<usize, usize> = HashMap::new();
+            for distance in tile.iter().flatten() {
+                *distances.entry(*distance).or_default() += 1;
+            }
+            for (distance, plots) in distances {
+                let mut reachable = usize::from(distance <= steps && distance % 2 == steps % 2);
+                if edge_x {
+                    reachable += count_line(distance, cost_x, steps);
+                }
+                if edge_y {
+                    reachable += count_line(distance, cost_y, steps);
+                }
+                if edge_x && edge_y {
+                    reachable += count_quadrant(distance, cost_x, cost_y, steps);
+                }
+                count += plots * reachable;
             }
         }
         count
     }
 }
 
-#[derive(PartialEq)]
-enum Tile {
-    Start,
-    Plot,
-    Rock,
+// How many of `distance + cost * k` for k >= 1 are at most `steps` and have
+// the same parity
+fn count_line(distance: usize, cost: usize, steps: usize) -> usize {
+    if cost == 0 || distance + cost > steps {
+        return 0;
+    }
+    let left = steps - distance;
+    let k = left / cost;
+    match (cost % 2, left % 2) {
+        (0, 1) => 0,
+        (0, _) => k,
+        (_, 1) => k.div_ceil(2),
+        _ => k / 2,
+    }
 }
 
-impl Tile {
-    fn parse(c: char) -> Option<Self> {
-        match c {
-            'S' => Some(Self::Start),
-            '.' => Some(Self::Plot),
-            '#' => Some(Self::Rock),
-            _ => None,
-        }
+// How many of `distance + cost_x * i + cost_y * j` for i, j >= 1 are at
+// most `steps` and have the same parity
+fn count_quadrant(distance: usize, cost_x: usize, cost_y: usize, steps: usize) -> usize {
+    if cost_x == 0 || cost_y == 0 || distance + cost_x + cost_y > steps {
+        return 0;
+    }
+    if cost_x != cost_y {
+        return (1..)
+            .map(|i| distance + cost_x * i)
+            .take_while(|distance| distance + cost_y <= steps)
+            .map(|distance| count_line(distance, cost_y, steps))
+            .sum();
     }
+
+    // With equal costs only n = i + j matters, and there are n - 1 ways to
+    // split each n. Sum those over the n that qualify.
+    let cost = cost_x;
+    let left = steps - distance;
+    let max = left / cost;
+    let (first, stride) = match (cost % 2, left % 2) {
+        (0, 1) => return 0,
+        (0, _) => (2, 1),
+        (_, 1) => (3, 2),
+        _ => (2, 2),
+    };
+    if max < first {
+        return 0;
+    }
+    let terms = (max - first) / stride + 1;
+    terms * (first - 1) + stride * terms * (terms - 1) / 2
 }
 
 #[cfg(test)]
@@ -147,13 +281,30 @@ mod tests {
 
     #[test]
     fn test_part_one() {
-        let result = part_one(&advent_of_code::template::read_file("examples", DAY));
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let result = Map::parse(&input).unwrap().count_reachable_plots(6);
         assert_eq!(result, Some(16));
     }
 
     #[test]
     fn test_part_two() {
-        let result = part_two(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, None);
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let map = Map::parse(&input).unwrap();
+        let expected = [
+            (6, 16),
+            (10, 50),
+            (50, 1594),
+            (100, 6536),
+            (500, 167004),
+            (1000, 668697),
+            (5000, 16733044),
+        ];
+        for (steps, plots) in expected {
+            assert_eq!(
+                map.count_reachable_plots(steps),
+                Some(plots),
+                "{steps} steps"
+            );
+        }
     }
 }
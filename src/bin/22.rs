@@ -0,0 +1,162 @@
+#![feature(let_chains)]
+advent_of_code::solution!(22);
+
+use advent_of_code::parse::{self, Cursor};
+use std::collections::VecDeque;
+
+pub fn part_one(input: &str) -> parse::Result<usize> {
+    let tower = Tower::settle(parse(input)?);
+    Ok((0..tower.len()).filter(|&i| tower.is_safe(i)).count())
+}
+
+pub fn part_two(input: &str) -> parse::Result<usize> {
+    let tower = Tower::settle(parse(input)?);
+    Ok((0..tower.len()).map(|i| tower.count_falling(i)).sum())
+}
+
+fn parse(input: &str) -> parse::Result<Vec<Brick>> {
+    parse::lines(input).map(Brick::parse).collect()
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Brick {
+    lo: [usize; 3],
+    hi: [usize; 3],
+}
+
+impl Brick {
+    fn parse(mut line: Cursor) -> parse::Result<Self> {
+        let mut ends = [[0; 3]; 2];
+        for (i, end) in ends.iter_mut().enumerate() {
+            if i > 0 {
+                line.tag("~")?;
+            }
+            for (j, value) in end.iter_mut().enumerate() {
+                if j > 0 {
+                    line.tag(",")?;
+                }
+                *value = line.number()?;
+            }
+        }
+        line.end()?;
+        let [[x0, y0, z0], [x1, y1, z1]] = ends;
+        Ok(Self {
+            lo: [x0.min(x1), y0.min(y1), z0.min(z1)],
+            hi: [x0.max(x1), y0.max(y1), z0.max(z1)],
+        })
+    }
+
+    fn height(&self) -> usize {
+        self.hi[2] - self.lo[2] + 1
+    }
+
+    // indices of the cells this brick covers in a top-down view
+    fn footprint(&self, width: usize) -> impl Iterator<Item = usize> + '_ {
+        (self.lo[1]..=self.hi[1])
+            .flat_map(move |y| (self.lo[0]..=self.hi[0]).map(move |x| y * width + x))
+    }
+}
+
+// Bricks after they've fallen, indexed from lowest to highest starting
+// point, along with which bricks rest directly on which.
+struct Tower {
+    below: Vec<Vec<usize>>,
+    above: Vec<Vec<usize>>,
+}
+
+impl Tower {
+    fn settle(mut bricks: Vec<Brick>) -> Self {
+        bricks.sort_by_key(|brick| brick.lo[2]);
+        let width = bricks
+            .iter()
+            .map(|brick| brick.hi[0] + 1)
+            .max()
+            .unwrap_or(0);
+        let depth = bricks
+            .iter()
+            .map(|brick| brick.hi[1] + 1)
+            .max()
+            .unwrap_or(0);
+
+        // height of the highest settled brick over each cell, and which
+        // brick that is
+        let mut top: Vec<(usize, Option<usize>)> = vec![(0, None); width * depth];
+        let mut below = vec![Vec::new(); bricks.len()];
+        let mut above = vec![Vec::new(); bricks.len()];
+
+        for (i, brick) in bricks.iter().enumerate() {
+            let floor = brick
+                .footprint(width)
+                .map(|cell| top[cell].0)
+                .max()
+                .unwrap_or(0);
+
+            for cell in brick.footprint(width) {
+                if let (height, Some(j)) = top[cell]
+                    && height == floor
+                    && !below[i].contains(&j)
+                {
+                    below[i].push(j);
+                    above[j].push(i);
+                }
+                top[cell] = (floor + brick.height(), Some(i));
+            }
+        }
+
+        Self { below, above }
+    }
+
+    fn len(&self) -> usize {
+        self.below.len()
+    }
+
+    // every brick resting on this one has another brick holding it up
+    fn is_safe(&self, brick: usize) -> bool {
+        self.above[brick]
+            .iter()
+            .all(|other| self.below[*other].len() > 1)
+    }
+
+    // number of other bricks that fall if this one is disintegrated
+    fn count_falling(&self, brick: usize) -> usize {
+        let mut fallen = vec![false; self.len()];
+        fallen[brick] = true;
+        let mut queue = VecDeque::from([brick]);
+        let mut count = 0;
+        while let Some(brick) = queue.pop_front() {
+            for &other in &self.above[brick] {
+                if !fallen[other] && self.below[other].iter().all(|support| fallen[*support]) {
+                    fallen[other] = true;
+                    count += 1;
+                    queue.push_back(other);
+                }
+            }
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_one() {
+        let result = part_one(&advent_of_code::template::read_file("examples", DAY));
+        assert_eq!(result, Ok(5));
+    }
+
+    #[test]
+    fn test_part_two() {
+        let result = part_two(&advent_of_code::template::read_file("examples", DAY));
+        assert_eq!(result, Ok(7));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = part_one("1,0,1~1,2,1\n0,0,2~2,0\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 2, column 10: expecting `,`");
+        let error = part_one("1,0,1~1,2,1\n0,0,2,2,0,2\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 2, column 6: expecting `~`");
+    }
+}
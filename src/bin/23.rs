@@ -0,0 +1,216 @@
+#![feature(let_chains)]
+advent_of_code::solution!(23);
+
+use std::collections::HashMap;
+
+pub fn part_one(input: &str) -> Option<usize> {
+    Map::parse(input)?.compress(Mode::Slippery)?.longest_path()
+}
+
+pub fn part_two(input: &str) -> Option<usize> {
+    Map::parse(input)?.compress(Mode::Dry)?.longest_path()
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Mode {
+    Slippery,
+    Dry,
+}
+
+type Point = (usize, usize);
+
+struct Map {
+    grid: Vec<Vec<u8>>,
+    start: Point,
+    end: Point,
+}
+
+impl Map {
+    fn parse(input: &str) -> Option<Self> {
+        let grid: Vec<Vec<u8>> = input.lines().map(|line| line.bytes().collect()).collect();
+        let start = (grid.first()?.iter().position(|&b| b == b'.')?, 0);
+        let end = (
+            grid.last()?.iter().position(|&b| b == b'.')?,
+            grid.len() - 1,
+        );
+        Some(Self { grid, start, end })
+    }
+
+    fn get(&self, (x, y): Point) -> u8 {
+        self.grid
+            .get(y)
+            .and_then(|row| row.get(x))
+            .copied()
+            .unwrap_or(b'#')
+    }
+
+    // the point one step from `point` in `heading`, if it can be walked on
+    fn step(&self, mode: Mode, point: Point, heading: Heading) -> Option<Point> {
+        let (dx, dy) = heading.diff();
+        let next = (
+            point.0.checked_add_signed(dx)?,
+            point.1.checked_add_signed(dy)?,
+        );
+        let tile = self.get(next);
+        if tile == b'#' {
+            return None;
+        }
+        if mode == Mode::Slippery {
+            for tile in [self.get(point), tile] {
+                if let Some(slope) = Heading::slope(tile)
+                    && slope != heading
+                {
+                    return None;
+                }
+            }
+        }
+        Some(next)
+    }
+
+    fn is_junction(&self, point: Point) -> bool {
+        point == self.start
+            || point == self.end
+            || Heading::ALL
+                .into_iter()
+                .filter(|heading| self.step(Mode::Dry, point, *heading).is_some())
+                .count()
+                > 2
+    }
+
+    // Collapses every corridor between junctions into a single weighted
+    // edge.
+    fn compress(&self, mode: Mode) -> Option<Trails> {
+        let mut ids = HashMap::new();
+        for (y, row) in self.grid.iter().enumerate() {
+            for x in 0..row.len() {
+                if self.get((x, y)) != b'#' && self.is_junction((x, y)) {
+                    ids.insert((x, y), ids.len());
+                }
+            }
+        }
+
+        // visited junctions are tracked in a u64
+        if ids.len() > 64 {
+            return None;
+        }
+
+        let mut edges = vec![Vec::new(); ids.len()];
+        for (&junction, &src) in ids.iter() {
+            for heading in Heading::ALL {
+                let Some(mut point) = self.step(mode, junction, heading) else {
+                    continue;
+                };
+                let mut prev = junction;
+                let mut steps = 1;
+                while !ids.contains_key(&point) {
+                    let Some(next) = Heading::ALL
+                        .into_iter()
+                        .filter_map(|heading| self.step(mode, point, heading))
+                        .find(|next| *next != prev)
+                    else {
+                        break;
+                    };
+                    (prev, point) = (point, next);
+                    steps += 1;
+                }
+                if let Some(&dst) = ids.get(&point) {
+                    edges[src].push((dst, steps));
+                }
+            }
+        }
+
+        let end = ids[&self.end];
+        let exits = edges
+            .iter()
+            .filter(|edges| edges.iter().any(|(dst, _)| *dst == end));
+        Some(Trails {
+            start: ids[&self.start],
+            end,
+            single_exit: exits.count() == 1,
+            edges,
+        })
+    }
+}
+
+struct Trails {
+    start: usize,
+    end: usize,
+    single_exit: bool,
+    edges: Vec<Vec<(usize, usize)>>,
+}
+
+impl Trails {
+    fn longest_path(&self) -> Option<usize> {
+        self.dfs(self.start, 1 << self.start)
+    }
+
+    fn dfs(&self, junction: usize, seen: u64) -> Option<usize> {
+        if junction == self.end {
+            return Some(0);
+        }
+
+        // If only one junction leads to the end, any path that passes it by
+        // is stuck, so head straight there.
+        if self.single_exit
+            && let Some((_, steps)) = self.edges[junction]
+                .iter()
+                .find(|(dst, _)| *dst == self.end)
+        {
+            return Some(*steps);
+        }
+
+        self.edges[junction]
+            .iter()
+            .filter(|(dst, _)| seen & (1 << dst) == 0)
+            .filter_map(|&(dst, steps)| Some(steps + self.dfs(dst, seen | 1 << dst)?))
+            .max()
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Heading {
+    N,
+    E,
+    S,
+    W,
+}
+
+impl Heading {
+    const ALL: [Heading; 4] = [Self::N, Self::E, Self::S, Self::W];
+
+    fn diff(&self) -> (isize, isize) {
+        match self {
+            Self::N => (0, -1),
+            Self::E => (1, 0),
+            Self::S => (0, 1),
+            Self::W => (-1, 0),
+        }
+    }
+
+    fn slope(tile: u8) -> Option<Self> {
+        match tile {
+            b'^' => Some(Self::N),
+            b'>' => Some(Self::E),
+            b'v' => Some(Self::S),
+            b'<' => Some(Self::W),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_one() {
+        let result = part_one(&advent_of_code::template::read_file("examples", DAY));
+        assert_eq!(result, Some(94));
+    }
+
+    #[test]
+    fn test_part_two() {
+        let result = part_two(&advent_of_code::template::read_file("examples", DAY));
+        assert_eq!(result, Some(154));
+    }
+}
@@ -0,0 +1,209 @@
+advent_of_code::solution!(24);
+
+use advent_of_code::parse::{self, Cursor};
+use num::{BigInt, BigRational, One, ToPrimitive, Zero};
+
+pub fn part_one(input: &str) -> Option<usize> {
+    count_crossings(input, 200_000_000_000_000, 400_000_000_000_000)
+}
+
+pub fn part_two(input: &str) -> Option<i64> {
+    let hail = parse(input).ok()?;
+    let rock = throw(&hail)?;
+    rock.position.iter().sum::<i128>().try_into().ok()
+}
+
+// Number of pairs of hailstone paths that cross in the future, within the
+// square `min..=max` in x and y.
+fn count_crossings(input: &str, min: i128, max: i128) -> Option<usize> {
+    let hail = parse(input).ok()?;
+    let mut count = 0;
+    for (i, a) in hail.iter().enumerate() {
+        for b in &hail[i + 1..] {
+            if a.crosses(b, min, max) {
+                count += 1;
+            }
+        }
+    }
+    Some(count)
+}
+
+fn parse(input: &str) -> parse::Result<Vec<Hailstone>> {
+    parse::lines(input).map(Hailstone::parse).collect()
+}
+
+type Vector = [i128; 3];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Hailstone {
+    position: Vector,
+    velocity: Vector,
+}
+
+impl Hailstone {
+    fn parse(mut line: Cursor) -> parse::Result<Self> {
+        fn vector(line: &mut Cursor) -> parse::Result<Vector> {
+            let x = line.number()?;
+            line.tag(",")?;
+            let y = line.number()?;
+            line.tag(",")?;
+            let z = line.number()?;
+            Ok([x, y, z])
+        }
+        let position = vector(&mut line)?;
+        line.tag("@")?;
+        let velocity = vector(&mut line)?;
+        line.end()?;
+        Ok(Self { position, velocity })
+    }
+
+    // Whether the xy paths of the two hailstones cross inside the test area
+    // at a time that is in the future for both of them.
+    fn crosses(&self, other: &Self, min: i128, max: i128) -> bool {
+        let ([px, py, _], [vx, vy, _]) = (self.position, self.velocity);
+        let ([qx, qy, _], [wx, wy, _]) = (other.position, other.velocity);
+
+        // Solving p + v·t = q + w·s gives t = (d × w) / (v × w) and
+        // s = (d × v) / (v × w) where d = q - p. Everything is kept as
+        // numerator over a positive denominator so the bounds checks stay
+        // exact.
+        let mut det = vx * wy - vy * wx;
+        if det == 0 {
+            return false;
+        }
+        let (dx, dy) = (qx - px, qy - py);
+        let mut t = dx * wy - dy * wx;
+        let mut s = dx * vy - dy * vx;
+        if det < 0 {
+            (det, t, s) = (-det, -t, -s);
+        }
+        if t < 0 || s < 0 {
+            return false;
+        }
+
+        let x = px * det + vx * t;
+        let y = py * det + vy * t;
+        let range = min * det..=max * det;
+        range.contains(&x) && range.contains(&y)
+    }
+
+    // Whether the two meet at the same point at some time t >= 0.
+    fn hits(&self, other: &Self) -> bool {
+        let d = sub(self.position, other.position);
+        let w = sub(other.velocity, self.velocity);
+        if cross(d, w) != [0; 3] {
+            return false;
+        }
+        // d = w·t, so any non-zero component of w gives t
+        match (0..3).find(|&i| w[i] != 0) {
+            Some(i) => d[i] % w[i] == 0 && d[i] / w[i] >= 0,
+            None => d == [0; 3],
+        }
+    }
+}
+
+// Finds the rock's starting position and velocity.
+//
+// The rock r + u·t hits hailstone i when (r - pᵢ) × (u - vᵢ) = 0. The r × u
+// term is the same for every hailstone, so subtracting the equations for two
+// hailstones leaves three that are linear in r and u:
+//
+//   r × (vⱼ - vᵢ) + (pⱼ - pᵢ) × u = pⱼ × vⱼ - pᵢ × vᵢ
+//
+// Two such pairs are enough to pin down all six unknowns.
+fn throw(hail: &[Hailstone]) -> Option<Hailstone> {
+    let candidates = hail.windows(3).find_map(|trio| {
+        let mut rows = equations(&trio[0], &trio[1]);
+        rows.extend(equations(&trio[0], &trio[2]));
+        solve(rows)
+    })?;
+
+    let mut rock = [0; 6];
+    for (value, solution) in rock.iter_mut().zip(candidates) {
+        if !solution.is_integer() {
+            return None;
+        }
+        *value = solution.to_integer().to_i128()?;
+    }
+    let rock = Hailstone {
+        position: [rock[0], rock[1], rock[2]],
+        velocity: [rock[3], rock[4], rock[5]],
+    };
+    hail.iter()
+        .all(|hailstone| rock.hits(hailstone))
+        .then_some(rock)
+}
+
+// The three equations from a pair of hailstones, as rows of
+// [rx, ry, rz, ux, uy, uz, rhs].
+fn equations(a: &Hailstone, b: &Hailstone) -> Vec<Vec<BigRational>> {
+    let [wx, wy, wz] = sub(b.velocity, a.velocity);
+    let [dx, dy, dz] = sub(b.position, a.position);
+    let rhs = sub(cross(b.position, b.velocity), cross(a.position, a.velocity));
+    let rows = [
+        [0, wz, -wy, 0, -dz, dy, rhs[0]],
+        [-wz, 0, wx, dz, 0, -dx, rhs[1]],
+        [wy, -wx, 0, -dy, dx, 0, rhs[2]],
+    ];
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .map(|value| BigRational::from_integer(BigInt::from(*value)))
+                .collect()
+        })
+        .collect()
+}
+
+// Solves an augmented square system by Gauss-Jordan elimination, or returns
+// `None` if it's singular.
+fn solve(mut rows: Vec<Vec<BigRational>>) -> Option<Vec<BigRational>> {
+    let n = rows.len();
+    for col in 0..n {
+        let pivot = (col..n).find(|&row| !rows[row][col].is_zero())?;
+        rows.swap(col, pivot);
+        let scale = BigRational::one() / &rows[col][col];
+        for value in rows[col].iter_mut() {
+            *value *= &scale;
+        }
+        let pivot = rows[col].clone();
+        for (i, row) in rows.iter_mut().enumerate() {
+            if i == col || row[col].is_zero() {
+                continue;
+            }
+            let factor = row[col].clone();
+            for (value, p) in row[col..].iter_mut().zip(&pivot[col..]) {
+                *value -= &factor * p;
+            }
+        }
+    }
+    Some(rows.into_iter().map(|row| row[n].clone()).collect())
+}
+
+fn sub(a: Vector, b: Vector) -> Vector {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: Vector, b: Vector) -> Vector {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_one() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        assert_eq!(count_crossings(&input, 7, 27), Some(2));
+    }
+
+    #[test]
+    fn test_part_two() {
+        let result = part_two(&advent_of_code::template::read_file("examples", DAY));
+        assert_eq!(result, Some(47));
+    }
+}
@@ -0,0 +1,114 @@
+advent_of_code::solution!(25);
+
+use advent_of_code::graph::{Graph, NodeId};
+use advent_of_code::parse;
+use std::collections::{HashMap, VecDeque};
+
+const CUT: usize = 3;
+
+pub fn part_one(input: &str) -> Option<usize> {
+    let graph = parse(input).ok()?;
+    let mut nodes = graph.nodes();
+    let src = nodes.next()?;
+
+    // Some node ends up on the other side of the cut from `src`, and for
+    // that node alone the max flow from `src` is exactly the cut size.
+    nodes.find_map(|dst| {
+        let side = Flow::new(&graph).min_cut(src, dst, CUT)?;
+        Some(side * (graph.len() - side))
+    })
+}
+
+pub fn part_two(_input: &str) -> Option<usize> {
+    None
+}
+
+// Every connection is added in both directions
+fn parse(input: &str) -> parse::Result<Graph<'_>> {
+    let mut graph = Graph::new();
+    for line in parse::lines(input) {
+        let (name, neighbors) = parse::adjacency(line, ":")?;
+        let src = graph.node(name);
+        for neighbor in neighbors {
+            let dst = graph.node(neighbor);
+            graph.add_edge(src, dst);
+            graph.add_edge(dst, src);
+        }
+    }
+    Ok(graph)
+}
+
+// Unit capacity flow over an undirected graph, where `flow[(a, b)]` is
+// always `-flow[(b, a)]`.
+struct Flow<'a, 'g> {
+    graph: &'g Graph<'a>,
+    flow: HashMap<(NodeId, NodeId), i32>,
+}
+
+impl<'a, 'g> Flow<'a, 'g> {
+    fn new(graph: &'g Graph<'a>) -> Self {
+        Self {
+            graph,
+            flow: HashMap::new(),
+        }
+    }
+
+    fn residual(&self, src: NodeId, dst: NodeId) -> i32 {
+        1 - self.flow.get(&(src, dst)).copied().unwrap_or(0)
+    }
+
+    // Pushes flow from `src` to `dst` one augmenting path at a time. If the
+    // max flow is exactly `size`, returns the number of nodes on `src`'s
+    // side of the minimum cut.
+    fn min_cut(&mut self, src: NodeId, dst: NodeId, size: usize) -> Option<usize> {
+        for pushed in 0..=size {
+            let prev = self.search(src);
+            if prev[dst.index()].is_none() {
+                // what's still reachable from `src` is its side of the cut
+                return (pushed == size).then(|| prev.iter().flatten().count());
+            }
+            let mut node = dst;
+            while node != src {
+                let before = prev[node.index()]?;
+                *self.flow.entry((before, node)).or_default() += 1;
+                *self.flow.entry((node, before)).or_default() -= 1;
+                node = before;
+            }
+        }
+        None
+    }
+
+    // BFS over edges with capacity left, returning each reached node's
+    // predecessor (`src` is its own).
+    fn search(&self, src: NodeId) -> Vec<Option<NodeId>> {
+        let mut prev = vec![None; self.graph.len()];
+        prev[src.index()] = Some(src);
+        let mut queue = VecDeque::from([src]);
+        while let Some(node) = queue.pop_front() {
+            for &next in self.graph.outputs(node) {
+                if prev[next.index()].is_none() && self.residual(node, next) > 0 {
+                    prev[next.index()] = Some(node);
+                    queue.push_back(next);
+                }
+            }
+        }
+        prev
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_one() {
+        let result = part_one(&advent_of_code::template::read_file("examples", DAY));
+        assert_eq!(result, Some(54));
+    }
+
+    #[test]
+    fn test_part_two() {
+        let result = part_two(&advent_of_code::template::read_file("examples", DAY));
+        assert_eq!(result, None);
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Write};
+
+/// A dense index standing in for a node's name in a [`Graph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// The node's index, suitable for indexing per-node `Vec`s.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl Display for NodeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+/// Maps names to [`NodeId`]s handed out in the order names are first seen.
+#[derive(Debug, Default, Clone)]
+pub struct Interner<'a> {
+    ids: HashMap<&'a str, NodeId>,
+    names: Vec<&'a str>,
+}
+
+impl<'a> Interner<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The id for `name`, assigning the next free one if it's new.
+    pub fn intern(&mut self, name: &'a str) -> NodeId {
+        *self.ids.entry(name).or_insert_with(|| {
+            self.names.push(name);
+            NodeId(self.names.len() - 1)
+        })
+    }
+
+    /// The id for `name`, if it has been interned.
+    pub fn get(&self, name: &str) -> Option<NodeId> {
+        self.ids.get(name).copied()
+    }
+
+    /// The name an id was assigned to.
+    ///
+    /// # Panics
+    /// If the id came from a different interner.
+    pub fn name(&self, id: NodeId) -> &'a str {
+        self.names[id.0]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+/// A directed graph of named nodes, stored as adjacency lists over
+/// [`NodeId`]s so that hot loops never hash a name.
+///
+/// Edges keep the order they were added in, and adding the same edge twice
+/// keeps both copies.
+///
+/// ```
+/// # use advent_of_code::graph::Graph;
+/// let mut graph = Graph::new();
+/// let (a, b, c) = (graph.node("a"), graph.node("b"), graph.node("c"));
+/// graph.add_edge(a, b);
+/// graph.add_edge(b, c);
+/// assert_eq!(graph.outputs(a), [b]);
+/// assert_eq!(graph.inputs(c), [b]);
+/// assert_eq!(graph.topological_sort(), Some(vec![a, b, c]));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Graph<'a> {
+    names: Interner<'a>,
+    outputs: Vec<Vec<NodeId>>,
+    inputs: Vec<Vec<NodeId>>,
+}
+
+impl<'a> Graph<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The id for the node called `name`, adding it if it's new.
+    pub fn node(&mut self, name: &'a str) -> NodeId {
+        let id = self.names.intern(name);
+        if id.0 == self.outputs.len() {
+            self.outputs.push(Vec::new());
+            self.inputs.push(Vec::new());
+        }
+        id
+    }
+
+    /// Adds an edge from `src` to `dst`.
+    pub fn add_edge(&mut self, src: NodeId, dst: NodeId) {
+        self.outputs[src.0].push(dst);
+        self.inputs[dst.0].push(src);
+    }
+
+    /// The id of the node called `name`, if there is one.
+    pub fn id(&self, name: &str) -> Option<NodeId> {
+        self.names.get(name)
+    }
+
+    pub fn name(&self, id: NodeId) -> &'a str {
+        self.names.name(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Every node, in id order.
+    pub fn nodes(&self) -> impl Iterator<Item = NodeId> {
+        (0..self.len()).map(NodeId)
+    }
+
+    /// The targets of edges leaving `id`.
+    pub fn outputs(&self, id: NodeId) -> &[NodeId] {
+        &self.outputs[id.0]
+    }
+
+    /// The sources of edges entering `id`.
+    pub fn inputs(&self, id: NodeId) -> &[NodeId] {
+        &self.inputs[id.0]
+    }
+
+    /// Orders the nodes so every edge points forward, or returns [`None`]
+    /// if the graph has a cycle.
+    pub fn topological_sort(&self) -> Option<Vec<NodeId>> {
+        // https://en.wikipedia.org/wiki/Topological_sorting#Kahn's_algorithm
+        let mut degrees: Vec<usize> = self.inputs.iter().map(Vec::len).collect();
+        let mut queue: VecDeque<NodeId> = self.nodes().filter(|id| degrees[id.0] == 0).collect();
+        let mut order = Vec::with_capacity(self.len());
+        while let Some(id) = queue.pop_front() {
+            order.push(id);
+            for dst in self.outputs(id) {
+                degrees[dst.0] -= 1;
+                if degrees[dst.0] == 0 {
+                    queue.push_back(*dst);
+                }
+            }
+        }
+        (order.len() == self.len()).then_some(order)
+    }
+
+    /// Partitions the nodes into strongly connected components.
+    ///
+    /// Components come out in reverse topological order: no edge leads from
+    /// a component to one that comes after it.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<NodeId>> {
+        // https://en.wikipedia.org/wiki/Tarjan%27s_strongly_connected_components_algorithm
+        // with an explicit stack of (node, next edge) in place of recursion
+        const UNVISITED: usize = usize::MAX;
+        let n = self.len();
+        let mut index = vec![UNVISITED; n];
+        let mut low = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack = Vec::new();
+        let mut components = Vec::new();
+        let mut next = 0;
+
+        for root in 0..n {
+            if index[root] != UNVISITED {
+                continue;
+            }
+            let mut calls = vec![(root, 0)];
+            index[root] = next;
+            low[root] = next;
+            next += 1;
+            stack.push(root);
+            on_stack[root] = true;
+
+            while let Some(&(v, edge)) = calls.last() {
+                if let Some(w) = self.outputs[v].get(edge).map(|id| id.0) {
+                    calls.last_mut().unwrap().1 += 1;
+                    if index[w] == UNVISITED {
+                        index[w] = next;
+                        low[w] = next;
+                        next += 1;
+                        stack.push(w);
+                        on_stack[w] = true;
+                        calls.push((w, 0));
+                    } else if on_stack[w] {
+                        low[v] = low[v].min(index[w]);
+                    }
+                    continue;
+                }
+
+                calls.pop();
+                if let Some(&(u, _)) = calls.last() {
+                    low[u] = low[u].min(low[v]);
+                }
+                if low[v] == index[v] {
+                    let mut component = Vec::new();
+                    while let Some(w) = stack.pop() {
+                        on_stack[w] = false;
+                        component.push(NodeId(w));
+                        if w == v {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+
+    /// Renders the graph in Graphviz's DOT language.
+    pub fn dot(&self) -> String {
+        self.dot_with(|_| String::new())
+    }
+
+    /// Renders the graph in Graphviz's DOT language, with extra attributes
+    /// for each node, e.g. `shape=box`.
+    pub fn dot_with(&self, attributes: impl Fn(NodeId) -> String) -> String {
+        let mut dot = String::from("digraph {\n");
+        for id in self.nodes() {
+            let attributes = attributes(id);
+            if attributes.is_empty() {
+                writeln!(dot, "    {:?};", self.name(id)).unwrap();
+            } else {
+                writeln!(dot, "    {:?} [{attributes}];", self.name(id)).unwrap();
+            }
+        }
+        for src in self.nodes() {
+            for dst in self.outputs(src) {
+                writeln!(dot, "    {:?} -> {:?};", self.name(src), self.name(*dst)).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(feature = "test_lib")]
+mod tests {
+    use super::{Graph, Interner};
+
+    fn parse(input: &str) -> Graph<'_> {
+        let mut graph = Graph::new();
+        for line in input.lines() {
+            let (src, dsts) = line.split_once(" -> ").unwrap();
+            let src = graph.node(src);
+            for dst in dsts.split(", ") {
+                let dst = graph.node(dst);
+                graph.add_edge(src, dst);
+            }
+        }
+        graph
+    }
+
+    #[test]
+    fn interner() {
+        let mut names = Interner::new();
+        let a = names.intern("a");
+        let b = names.intern("b");
+        assert_eq!(names.intern("a"), a);
+        assert_eq!((a.index(), b.index()), (0, 1));
+        assert_eq!(names.get("b"), Some(b));
+        assert_eq!(names.get("c"), None);
+        assert_eq!(names.name(b), "b");
+        assert_eq!(names.len(), 2);
+    }
+
+    #[test]
+    fn edges() {
+        let graph = parse("a -> b, c\nb -> c, c");
+        let [a, b, c] = ["a", "b", "c"].map(|name| graph.id(name).unwrap());
+        assert_eq!(graph.outputs(a), [b, c]);
+        assert_eq!(graph.outputs(b), [c, c]);
+        assert_eq!(graph.inputs(c), [a, b, b]);
+        assert!(graph.outputs(c).is_empty());
+    }
+
+    #[test]
+    fn topological_sort() {
+        let graph =
+            parse("shirt -> tie, belt\ntie -> jacket\npants -> shoes, belt\nbelt -> jacket");
+        let order = graph.topological_sort().unwrap();
+        let position = |name| order.iter().position(|id| graph.name(*id) == name);
+        for src in graph.nodes() {
+            for dst in graph.outputs(src) {
+                assert!(position(graph.name(src)) < position(graph.name(*dst)));
+            }
+        }
+        assert_eq!(parse("a -> b\nb -> a").topological_sort(), None);
+    }
+
+    #[test]
+    fn strongly_connected_components() {
+        let graph = parse("a -> b\nb -> c\nc -> a, d\nd -> e\ne -> d, f\nf -> f");
+        let mut components: Vec<Vec<&str>> = graph
+            .strongly_connected_components()
+            .into_iter()
+            .map(|component| {
+                let mut names: Vec<_> = component.into_iter().map(|id| graph.name(id)).collect();
+                names.sort();
+                names
+            })
+            .collect();
+        assert_eq!(components[0], ["f"]);
+        components.sort();
+        assert_eq!(components, [vec!["a", "b", "c"], vec!["d", "e"], vec!["f"]]);
+    }
+
+    #[test]
+    fn dot() {
+        let graph = parse("a -> b");
+        assert_eq!(
+            graph.dot(),
+            "digraph {\n    \"a\";\n    \"b\";\n    \"a\" -> \"b\";\n}\n"
+        );
+        let dot = graph.dot_with(|id| {
+            if graph.name(id) == "a" {
+                "shape=box".into()
+            } else {
+                String::new()
+            }
+        });
+        assert!(dot.contains("    \"a\" [shape=box];\n    \"b\";\n"));
+    }
+}
+
+/* -------------------------------------------------------------------------- */
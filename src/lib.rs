@@ -1,4 +1,10 @@
 mod day;
+pub mod graph;
+pub mod math;
+pub mod memo;
+pub mod parse;
+pub mod polygon;
+pub mod sequence;
 pub mod template;
 
 pub use day::*;
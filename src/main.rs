@@ -21,6 +21,8 @@ mod args {
             release: bool,
             time: bool,
             submit: Option<u8>,
+            mode: Option<String>,
+            arg: Option<String>,
         },
         All {
             release: bool,
@@ -50,6 +52,8 @@ mod args {
                 release: args.contains("--release"),
                 submit: args.opt_value_from_str("--submit")?,
                 time: args.contains("--time"),
+                mode: args.opt_value_from_str("--mode")?,
+                arg: args.opt_value_from_str("--arg")?,
             },
             Some(x) => {
                 eprintln!("Unknown command: {x}");
@@ -86,7 +90,9 @@ fn main() {
                 release,
                 time,
                 submit,
-            } => solve::handle(day, release, time, submit),
+                mode,
+                arg,
+            } => solve::handle(day, release, time, submit, mode, arg),
         },
     };
 }
@@ -0,0 +1,213 @@
+use num::Integer;
+
+/// The greatest common divisor of all the values, or [`None`] if there are
+/// none.
+pub fn gcd_all<T, I>(values: I) -> Option<T>
+where
+    T: Integer,
+    I: IntoIterator<Item = T>,
+{
+    values.into_iter().reduce(|acc, value| acc.gcd(&value))
+}
+
+/// The least common multiple of all the values, or [`None`] if there are
+/// none.
+///
+/// ```
+/// # use advent_of_code::math::lcm_all;
+/// assert_eq!(lcm_all([4u64, 6, 10]), Some(60));
+/// assert_eq!(lcm_all(Vec::<u64>::new()), None);
+/// ```
+pub fn lcm_all<T, I>(values: I) -> Option<T>
+where
+    T: Integer,
+    I: IntoIterator<Item = T>,
+{
+    values.into_iter().reduce(|acc, value| acc.lcm(&value))
+}
+
+/// Returns `(g, x, y)` such that `a * x + b * y = g`, where `g` is the
+/// non-negative greatest common divisor of `a` and `b`.
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut r0, mut r1) = (a, b);
+    let (mut x0, mut x1) = (1, 0);
+    let (mut y0, mut y1) = (0, 1);
+    while r1 != 0 {
+        let q = r0.div_euclid(r1);
+        (r0, r1) = (r1, r0 - q * r1);
+        (x0, x1) = (x1, x0 - q * x1);
+        (y0, y1) = (y1, y0 - q * y1);
+    }
+    if r0 < 0 {
+        (-r0, -x0, -y0)
+    } else {
+        (r0, x0, y0)
+    }
+}
+
+/// The inverse of `a` modulo `m`, if `a` and `m` are coprime.
+pub fn mod_inverse(a: i128, m: i128) -> Option<i128> {
+    let (g, x, _) = extended_gcd(a.rem_euclid(m), m);
+    (g == 1).then(|| x.rem_euclid(m))
+}
+
+/// `a * b` modulo `m`, without overflowing for any `m` that fits in an
+/// `i64`.
+pub fn mul_mod(a: i128, b: i128, m: i128) -> i128 {
+    (a.rem_euclid(m) * b.rem_euclid(m)).rem_euclid(m)
+}
+
+/// `base` raised to `exp`, modulo `m`.
+pub fn pow_mod(base: i128, mut exp: u64, m: i128) -> i128 {
+    let mut base = base.rem_euclid(m);
+    let mut result = 1 % m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+/// The set of integers `x` such that `x ≡ residue (mod modulus)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Congruence {
+    pub residue: i128,
+    pub modulus: i128,
+}
+
+impl Congruence {
+    /// Creates a [`Congruence`], normalizing the residue into
+    /// `0..modulus`.
+    ///
+    /// # Panics
+    /// If the modulus isn't positive.
+    pub fn new(residue: i128, modulus: i128) -> Self {
+        assert!(modulus > 0, "modulus must be positive, got {modulus}");
+        Self {
+            residue: residue.rem_euclid(modulus),
+            modulus,
+        }
+    }
+
+    /// Whether `x` satisfies this congruence.
+    pub fn contains(&self, x: i128) -> bool {
+        x.rem_euclid(self.modulus) == self.residue
+    }
+
+    /// The smallest solution that is at least `min`.
+    pub fn at_least(&self, min: i128) -> i128 {
+        min + (self.residue - min).rem_euclid(self.modulus)
+    }
+
+    /// The congruence satisfied by exactly the integers that satisfy both
+    /// `self` and `other`, or [`None`] if no integer does.
+    ///
+    /// The moduli don't need to be coprime.
+    pub fn combine(self, other: Self) -> Option<Self> {
+        let (g, p, _) = extended_gcd(self.modulus, other.modulus);
+        let diff = other.residue - self.residue;
+        if diff % g != 0 {
+            return None;
+        }
+        // self.residue + self.modulus * k ≡ other.residue (mod other.modulus)
+        //   => (self.modulus / g) * k ≡ diff / g (mod other.modulus / g)
+        // and p is the inverse of self.modulus / g modulo other.modulus / g
+        let step = other.modulus / g;
+        let k = mul_mod(diff / g, p, step);
+        let modulus = self.modulus / g * other.modulus;
+        Some(Self::new(self.residue + self.modulus * k, modulus))
+    }
+}
+
+/// Solves a system of congruences with the Chinese Remainder Theorem,
+/// returning [`None`] if it has no solution.
+///
+/// The moduli don't need to be pairwise coprime. An empty system is
+/// satisfied by every integer.
+///
+/// ```
+/// # use advent_of_code::math::{crt, Congruence};
+/// let solution = crt([Congruence::new(2, 6), Congruence::new(5, 9)]).unwrap();
+/// assert_eq!(solution, Congruence::new(14, 18));
+/// assert_eq!(crt([Congruence::new(0, 4), Congruence::new(1, 6)]), None);
+/// ```
+pub fn crt<I>(congruences: I) -> Option<Congruence>
+where
+    I: IntoIterator<Item = Congruence>,
+{
+    congruences
+        .into_iter()
+        .try_fold(Congruence::new(0, 1), Congruence::combine)
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(feature = "test_lib")]
+mod tests {
+    use super::{crt, extended_gcd, gcd_all, lcm_all, mod_inverse, pow_mod, Congruence};
+
+    #[test]
+    fn iterators() {
+        assert_eq!(gcd_all([12, 18, 30]), Some(6));
+        assert_eq!(lcm_all([2u64, 3, 4, 5]), Some(60));
+        assert_eq!(gcd_all(Vec::<i64>::new()), None);
+    }
+
+    #[test]
+    fn bezout() {
+        for (a, b) in [(240, 46), (-240, 46), (17, 0), (0, 5), (7, 13)] {
+            let (g, x, y) = extended_gcd(a, b);
+            assert!(g >= 0);
+            assert_eq!(a * x + b * y, g);
+        }
+        assert_eq!(extended_gcd(240, 46).0, 2);
+    }
+
+    #[test]
+    fn modular() {
+        assert_eq!(mod_inverse(3, 11), Some(4));
+        assert_eq!(mod_inverse(-3, 11), Some(7));
+        assert_eq!(mod_inverse(6, 9), None);
+        assert_eq!(pow_mod(2, 62, 1_000_000_007), (1i128 << 62) % 1_000_000_007);
+        assert_eq!(pow_mod(5, 0, 1), 0);
+    }
+
+    #[test]
+    fn chinese_remainder() {
+        let coprime = crt([
+            Congruence::new(2, 3),
+            Congruence::new(3, 5),
+            Congruence::new(2, 7),
+        ]);
+        assert_eq!(coprime, Some(Congruence::new(23, 105)));
+
+        let shared = crt([Congruence::new(3, 4), Congruence::new(5, 6)]);
+        assert_eq!(shared, Some(Congruence::new(11, 12)));
+
+        assert_eq!(crt([]), Some(Congruence::new(0, 1)));
+        assert_eq!(crt([Congruence::new(1, 2), Congruence::new(0, 4)]), None);
+    }
+
+    #[test]
+    fn large_moduli() {
+        let (a, b) = (999_999_999_989, 999_999_999_959);
+        let solution = crt([Congruence::new(5, a), Congruence::new(7, b)]).unwrap();
+        assert_eq!(solution.modulus, a * b);
+        assert_eq!(solution.residue % a, 5);
+        assert_eq!(solution.residue % b, 7);
+    }
+
+    #[test]
+    fn offsets() {
+        let every_third = Congruence::new(2, 3);
+        assert_eq!(every_third.at_least(0), 2);
+        assert_eq!(every_third.at_least(3), 5);
+        assert_eq!(every_third.at_least(5), 5);
+        assert_eq!(every_third.at_least(-4), -4);
+    }
+}
+
+/* -------------------------------------------------------------------------- */
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::Hash;
+
+/// A memoized recursive function.
+///
+/// `key` projects the function's arguments onto the cache key, which lets
+/// arguments that borrow (e.g. slices) be cached by something cheap like
+/// their lengths. `body` receives the [`Memo`] itself so that recursive
+/// calls go back through the cache.
+///
+/// Results are kept in a [`Store`]; [`Memo::new`] uses a [`HashMap`], and
+/// [`Memo::dense`] uses a `Vec` for keys that are already small indices.
+///
+/// ```
+/// # use advent_of_code::memo::Memo;
+/// fn fib(memo: &mut Memo<u64, u64, u64>, n: u64) -> u64 {
+///     if n < 2 {
+///         return n;
+///     }
+///     memo.call(n - 1) + memo.call(n - 2)
+/// }
+///
+/// let mut memo = Memo::new(|n: &u64| *n, fib);
+/// assert_eq!(memo.call(90), 2880067194370816120);
+/// assert_eq!(memo.stats().misses, 91);
+/// ```
+pub struct Memo<A, K, V, S = HashMap<K, V>> {
+    key: fn(&A) -> K,
+    body: fn(&mut Memo<A, K, V, S>, A) -> V,
+    store: S,
+    stats: Stats,
+}
+
+impl<A, K, V> Memo<A, K, V, HashMap<K, V>>
+where
+    K: Hash + Eq,
+{
+    /// Creates a [`Memo`] backed by a [`HashMap`].
+    pub fn new(key: fn(&A) -> K, body: fn(&mut Self, A) -> V) -> Self {
+        Self::with_store(HashMap::new(), key, body)
+    }
+}
+
+impl<A, V> Memo<A, usize, V, Vec<Option<V>>> {
+    /// Creates a [`Memo`] backed by a `Vec` with room for `len` keys; it
+    /// grows if a key lands past the end.
+    pub fn dense(len: usize, key: fn(&A) -> usize, body: fn(&mut Self, A) -> V) -> Self {
+        let mut store = Vec::new();
+        store.resize_with(len, || None);
+        Self::with_store(store, key, body)
+    }
+}
+
+impl<A, K, V, S> Memo<A, K, V, S>
+where
+    S: Store<K, V>,
+{
+    /// Creates a [`Memo`] with a custom [`Store`].
+    pub fn with_store(store: S, key: fn(&A) -> K, body: fn(&mut Self, A) -> V) -> Self {
+        Self {
+            key,
+            body,
+            store,
+            stats: Stats::default(),
+        }
+    }
+
+    /// Calls the function, returning a cached result for the argument's
+    /// key if there is one.
+    pub fn call(&mut self, arg: A) -> V
+    where
+        V: Clone,
+    {
+        let key = (self.key)(&arg);
+        if let Some(value) = self.store.get(&key) {
+            self.stats.hits += 1;
+            return value.clone();
+        }
+        self.stats.misses += 1;
+        let value = (self.body)(self, arg);
+        self.store.insert(key, value.clone());
+        value
+    }
+
+    /// Cache hits and misses so far.
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+}
+
+/// Storage for a [`Memo`]'s results.
+pub trait Store<K, V> {
+    fn get(&self, key: &K) -> Option<&V>;
+    fn insert(&mut self, key: K, value: V);
+}
+
+impl<K, V> Store<K, V> for HashMap<K, V>
+where
+    K: Hash + Eq,
+{
+    fn get(&self, key: &K) -> Option<&V> {
+        HashMap::get(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        HashMap::insert(self, key, value);
+    }
+}
+
+impl<V> Store<usize, V> for Vec<Option<V>> {
+    fn get(&self, key: &usize) -> Option<&V> {
+        self.as_slice().get(*key)?.as_ref()
+    }
+
+    fn insert(&mut self, key: usize, value: V) {
+        if key >= self.len() {
+            self.resize_with(key + 1, || None);
+        }
+        self[key] = Some(value);
+    }
+}
+
+/// Cache hit and miss counts for a [`Memo`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} hits, {} misses", self.hits, self.misses)
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(feature = "test_lib")]
+mod tests {
+    use super::{Memo, Stats};
+
+    fn paths(
+        memo: &mut Memo<(usize, usize), usize, u64, Vec<Option<u64>>>,
+        at: (usize, usize),
+    ) -> u64 {
+        match at {
+            (0, _) | (_, 0) => 1,
+            (x, y) => memo.call((x - 1, y)) + memo.call((x, y - 1)),
+        }
+    }
+
+    #[test]
+    fn hashed() {
+        let mut memo = Memo::new(
+            |&(x, y): &(usize, usize)| (x.min(y), x.max(y)),
+            |memo, (x, y)| match (x, y) {
+                (0, _) | (_, 0) => 1u64,
+                (x, y) => memo.call((x - 1, y)) + memo.call((x, y - 1)),
+            },
+        );
+        assert_eq!(memo.call((16, 16)), 601080390);
+        assert_eq!(
+            memo.stats(),
+            Stats {
+                hits: 121,
+                misses: 152,
+            }
+        );
+    }
+
+    #[test]
+    fn dense() {
+        let mut memo = Memo::dense(0, |&(x, y)| x * 17 + y, paths);
+        assert_eq!(memo.call((16, 16)), 601080390);
+        assert_eq!(
+            memo.stats(),
+            Stats {
+                hits: 225,
+                misses: 288,
+            }
+        );
+    }
+}
+
+/* -------------------------------------------------------------------------- */
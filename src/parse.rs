@@ -0,0 +1,413 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// The result of parsing puzzle input.
+pub type Result<T> = std::result::Result<T, ParseError>;
+
+/// An error which can be returned when parsing puzzle input, pointing at the
+/// line and column (both starting from 1) where parsing went wrong.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Error for ParseError {}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+/// A position in the puzzle input along with the text that follows it.
+///
+/// Parsing methods consume text from the front of the cursor and leave it
+/// where it was on failure, so errors point at the text that didn't match.
+///
+/// ```
+/// # use advent_of_code::parse::Cursor;
+/// let mut line = Cursor::new("Card 1: 41 48 | 83 86");
+/// line.tag("Card")?;
+/// let id: u32 = line.number()?;
+/// line.tag(":")?;
+/// let winners: Vec<u32> = line.until("|")?.numbers()?;
+/// let given: Vec<u32> = line.numbers()?;
+/// assert_eq!((id, winners, given), (1, vec![41, 48], vec![83, 86]));
+///
+/// let error = Cursor::new("Card x").tag("Game").unwrap_err();
+/// assert_eq!(error.to_string(), "line 1, column 1: expecting `Game`");
+/// # Ok::<(), advent_of_code::parse::ParseError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor<'a> {
+    rest: &'a str,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Cursor<'a> {
+    /// Creates a [`Cursor`] at the start of the input.
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            rest: input,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    /// The text that hasn't been consumed yet.
+    pub fn rest(&self) -> &'a str {
+        self.rest
+    }
+
+    /// Whether only whitespace is left.
+    pub fn is_empty(&self) -> bool {
+        self.rest.trim().is_empty()
+    }
+
+    /// An error at the cursor's position.
+    pub fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.column,
+            message: message.into(),
+        }
+    }
+
+    /// Consumes `tag`, after skipping any leading whitespace.
+    pub fn tag(&mut self, tag: &str) -> Result<()> {
+        let mut cursor = self.trim_start();
+        if !cursor.rest.starts_with(tag) {
+            return Err(cursor.error(format!("expecting `{tag}`")));
+        }
+        cursor.advance(tag.len());
+        *self = cursor;
+        Ok(())
+    }
+
+    /// Consumes the next run of non-whitespace characters.
+    pub fn word(&mut self) -> Result<&'a str> {
+        let mut cursor = self.trim_start();
+        let len = cursor
+            .rest
+            .find(char::is_whitespace)
+            .unwrap_or(cursor.rest.len());
+        if len == 0 {
+            return Err(cursor.error("expecting a word"));
+        }
+        let word = &cursor.rest[..len];
+        cursor.advance(len);
+        *self = cursor;
+        Ok(word)
+    }
+
+    /// Consumes the next number, which may be negative.
+    pub fn number<T: FromStr>(&mut self) -> Result<T> {
+        let mut cursor = self.trim_start();
+        let sign = usize::from(cursor.rest.starts_with('-'));
+        let len = cursor.rest[sign..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(cursor.rest.len() - sign)
+            + sign;
+        let number = cursor.rest[..len]
+            .parse()
+            .map_err(|_| cursor.error("expecting a number"))?;
+        cursor.advance(len);
+        *self = cursor;
+        Ok(number)
+    }
+
+    /// Consumes whitespace-separated numbers up to the end of the cursor.
+    pub fn numbers<T: FromStr>(&mut self) -> Result<Vec<T>> {
+        let mut numbers = Vec::new();
+        while !self.is_empty() {
+            numbers.push(self.number()?);
+        }
+        Ok(numbers)
+    }
+
+    /// Consumes everything up to and including `delimiter`, returning a
+    /// [`Cursor`] over the text before it.
+    pub fn until(&mut self, delimiter: &str) -> Result<Cursor<'a>> {
+        let Some(len) = self.rest.find(delimiter) else {
+            return Err(self.error(format!("expecting `{delimiter}`")));
+        };
+        let head = self.take(len);
+        self.advance(delimiter.len());
+        Ok(head)
+    }
+
+    /// Consumes the rest of the current line, including its line break.
+    pub fn line(&mut self) -> Result<Cursor<'a>> {
+        if self.rest.is_empty() {
+            return Err(self.error("unexpected end of input"));
+        }
+        let len = self.rest.find('\n').unwrap_or(self.rest.len());
+        let line = self.take(len);
+        if !self.rest.is_empty() {
+            self.advance(1);
+        }
+        Ok(line.trim_end_matches('\r'))
+    }
+
+    /// Succeeds if only whitespace is left.
+    pub fn end(&self) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let cursor = self.trim_start();
+        let len = cursor.rest.find('\n').unwrap_or(cursor.rest.len());
+        Err(cursor.error(format!("unexpected `{}`", &cursor.rest[..len])))
+    }
+
+    /// Splits the rest of the cursor on `delimiter`, keeping track of where
+    /// each piece starts.
+    pub fn split(self, delimiter: &'a str) -> impl Iterator<Item = Cursor<'a>> {
+        let mut cursor = Some(self);
+        std::iter::from_fn(move || {
+            let mut current = cursor?;
+            match current.rest.find(delimiter) {
+                Some(len) => {
+                    let piece = current.take(len);
+                    current.advance(delimiter.len());
+                    cursor = Some(current);
+                    Some(piece)
+                }
+                None => cursor.take(),
+            }
+        })
+    }
+
+    /// Splits the rest of the cursor into lines, ignoring a trailing line
+    /// break.
+    pub fn lines(self) -> impl Iterator<Item = Cursor<'a>> {
+        let mut cursor = self;
+        std::iter::from_fn(move || cursor.line().ok())
+    }
+
+    /// Splits the rest of the cursor into blocks separated by blank lines.
+    pub fn blocks(self) -> impl Iterator<Item = Cursor<'a>> {
+        let mut cursor = self;
+        std::iter::from_fn(move || {
+            cursor.skip_blank_lines();
+            if cursor.rest.is_empty() {
+                return None;
+            }
+            let len = cursor.rest.find("\n\n").unwrap_or(cursor.rest.len());
+            Some(cursor.take(len))
+        })
+    }
+
+    /// A copy of the cursor with leading whitespace skipped.
+    pub fn trim_start(&self) -> Self {
+        let mut cursor = *self;
+        let len = cursor.rest.len() - cursor.rest.trim_start().len();
+        cursor.advance(len);
+        cursor
+    }
+
+    fn trim_end_matches(mut self, c: char) -> Self {
+        self.rest = self.rest.trim_end_matches(c);
+        self
+    }
+
+    fn skip_blank_lines(&mut self) {
+        while self.rest.starts_with('\n') || self.rest.starts_with("\r\n") {
+            self.line().ok();
+        }
+    }
+
+    // Takes the first `len` bytes as a new cursor, advancing past them.
+    fn take(&mut self, len: usize) -> Self {
+        let head = Self {
+            rest: &self.rest[..len],
+            ..*self
+        };
+        self.advance(len);
+        head
+    }
+
+    fn advance(&mut self, len: usize) {
+        for c in self.rest[..len].chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+        self.rest = &self.rest[len..];
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+/// Splits the input into lines, keeping track of line numbers.
+pub fn lines(input: &str) -> impl Iterator<Item = Cursor<'_>> {
+    Cursor::new(input).lines()
+}
+
+/// Splits the input into blocks separated by blank lines, keeping track of
+/// line numbers.
+pub fn blocks(input: &str) -> impl Iterator<Item = Cursor<'_>> {
+    Cursor::new(input).blocks()
+}
+
+/// Parses a `key<separator>value` line, e.g. `seeds: 79 14 55 13`,
+/// returning the trimmed key and a [`Cursor`] over the value.
+pub fn key_value<'a>(mut line: Cursor<'a>, separator: &str) -> Result<(&'a str, Cursor<'a>)> {
+    let key = line.until(separator)?.rest().trim();
+    if key.is_empty() {
+        return Err(line.error("expecting a key"));
+    }
+    Ok((key, line))
+}
+
+/// Parses an adjacency line such as `name -> a, b` or `name: a b`,
+/// returning the name and its neighbors.
+pub fn adjacency<'a>(line: Cursor<'a>, arrow: &str) -> Result<(&'a str, Vec<&'a str>)> {
+    let (name, mut rest) = key_value(line, arrow)?;
+    let mut neighbors = Vec::new();
+    while !rest.is_empty() {
+        let word = rest.word()?;
+        for neighbor in word.split(',').filter(|neighbor| !neighbor.is_empty()) {
+            neighbors.push(neighbor);
+        }
+    }
+    Ok((name, neighbors))
+}
+
+/// Parses a rectangular grid of characters, converting each one with
+/// `tile`.
+pub fn grid<T>(input: Cursor<'_>, tile: impl Fn(char) -> Option<T>) -> Result<Vec<Vec<T>>> {
+    let mut rows: Vec<Vec<T>> = Vec::new();
+    for line in input.lines() {
+        let mut cursor = line;
+        let mut row = Vec::new();
+        for c in line.rest().chars() {
+            let Some(value) = tile(c) else {
+                return Err(cursor.error(format!("unexpected `{c}`")));
+            };
+            row.push(value);
+            cursor.advance(c.len_utf8());
+        }
+        let expected = rows.first().map_or(row.len(), Vec::len);
+        if row.len() != expected {
+            return Err(line.error(format!(
+                "row has {} columns, expecting {expected}",
+                row.len()
+            )));
+        }
+        rows.push(row);
+    }
+    if rows.is_empty() {
+        return Err(input.error("expecting a grid"));
+    }
+    Ok(rows)
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(feature = "test_lib")]
+mod tests {
+    use super::{adjacency, blocks, grid, key_value, lines, Cursor};
+
+    #[test]
+    fn tokens() {
+        let mut cursor = Cursor::new("Game 12: 3 blue, -4 red");
+        cursor.tag("Game").unwrap();
+        assert_eq!(cursor.number::<u32>(), Ok(12));
+        cursor.tag(":").unwrap();
+        let mut draws = cursor.split(",");
+        let mut first = draws.next().unwrap();
+        assert_eq!(first.number::<i32>(), Ok(3));
+        assert_eq!(first.word(), Ok("blue"));
+        assert!(first.end().is_ok());
+        let mut second = draws.next().unwrap();
+        assert_eq!(second.number::<i32>(), Ok(-4));
+        assert!(draws.next().is_none());
+
+        let error = Cursor::new("x = 5").number::<u32>().unwrap_err();
+        assert_eq!((error.line, error.column), (1, 1));
+        assert_eq!(error.to_string(), "line 1, column 1: expecting a number");
+    }
+
+    #[test]
+    fn line_numbers() {
+        let input = "a\nb\n\nc 1\nd x\n";
+        let found: Vec<_> = lines(input).map(|line| line.rest()).collect();
+        assert_eq!(found, ["a", "b", "", "c 1", "d x"]);
+
+        let mut last = lines(input).last().unwrap();
+        last.tag("d").unwrap();
+        let error = last.number::<u8>().unwrap_err();
+        assert_eq!(error.to_string(), "line 5, column 3: expecting a number");
+    }
+
+    #[test]
+    fn blank_line_blocks() {
+        let input = "seeds: 1 2\n\nsoil map:\n1 2 3\n4 5 6\n\nfertilizer map:\n7 8 x\n";
+        let mut found = blocks(input);
+
+        let (key, mut value) = key_value(found.next().unwrap(), ":").unwrap();
+        assert_eq!(key, "seeds");
+        assert_eq!(value.numbers::<u32>(), Ok(vec![1, 2]));
+
+        let mut soil = found.next().unwrap();
+        assert_eq!(soil.line().unwrap().until(" map:").unwrap().rest(), "soil");
+        let rows: Vec<Vec<u32>> = soil
+            .lines()
+            .map(|mut line| line.numbers().unwrap())
+            .collect();
+        assert_eq!(rows, [[1, 2, 3], [4, 5, 6]]);
+
+        let mut fertilizer = found.next().unwrap();
+        fertilizer.line().unwrap();
+        let error = fertilizer.line().unwrap().numbers::<u32>().unwrap_err();
+        assert_eq!(error.to_string(), "line 8, column 5: expecting a number");
+        assert!(found.next().is_none());
+    }
+
+    #[test]
+    fn adjacency_lines() {
+        let line = Cursor::new("%a -> inv, con");
+        assert_eq!(adjacency(line, "->"), Ok(("%a", vec!["inv", "con"])));
+
+        let line = Cursor::new("jqt: rhn xhk nvd");
+        assert_eq!(adjacency(line, ":"), Ok(("jqt", vec!["rhn", "xhk", "nvd"])));
+
+        let error = adjacency(Cursor::new("broadcaster"), "->").unwrap_err();
+        assert_eq!(error.to_string(), "line 1, column 1: expecting `->`");
+    }
+
+    #[test]
+    fn grids() {
+        let tile = |c| match c {
+            '.' => Some(false),
+            '#' => Some(true),
+            _ => None,
+        };
+        let parsed = grid(Cursor::new(".#\n#.\n"), tile).unwrap();
+        assert_eq!(parsed, [[false, true], [true, false]]);
+
+        let error = grid(Cursor::new("..\n.?\n"), tile).unwrap_err();
+        assert_eq!(error.to_string(), "line 2, column 2: unexpected `?`");
+
+        let error = grid(Cursor::new("..\n...\n"), tile).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 1: row has 3 columns, expecting 2"
+        );
+    }
+}
+
+/* -------------------------------------------------------------------------- */
@@ -0,0 +1,169 @@
+use num::{Integer, Signed};
+
+/// A closed polygon on the integer lattice.
+///
+/// Vertices are joined in order, and the last vertex is joined back to the
+/// first. Repeating the first vertex at the end of the list is allowed and
+/// doesn't change the result.
+///
+/// The coordinate type only needs to be a signed integer, so loops whose
+/// shoelace sums don't fit in an `i64` can be measured with `i128`.
+///
+/// ```
+/// # use advent_of_code::polygon::Polygon;
+/// let square = Polygon::new(vec![(0, 0), (2, 0), (2, 2), (0, 2)]);
+/// assert_eq!(square.area(), 4);
+/// assert_eq!(square.boundary(), 8);
+/// assert_eq!(square.interior(), 1);
+/// assert_eq!(square.enclosed(), 9);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polygon<T> {
+    vertices: Vec<(T, T)>,
+}
+
+impl<T> Polygon<T>
+where
+    T: Integer + Signed + Copy,
+{
+    /// Creates a [`Polygon`] from its vertices.
+    pub fn new(vertices: Vec<(T, T)>) -> Self {
+        Self { vertices }
+    }
+
+    /// Creates a [`Polygon`] by walking from the origin, where each step is
+    /// a `(dx, dy)` direction and the number of times to take it.
+    pub fn from_steps<I>(steps: I) -> Self
+    where
+        I: IntoIterator<Item = ((T, T), T)>,
+    {
+        let mut point = (T::zero(), T::zero());
+        let mut vertices = vec![point];
+        for ((dx, dy), distance) in steps {
+            point = (point.0 + dx * distance, point.1 + dy * distance);
+            vertices.push(point);
+        }
+        Self { vertices }
+    }
+
+    /// The polygon's vertices.
+    pub fn vertices(&self) -> &[(T, T)] {
+        &self.vertices
+    }
+
+    /// Twice the signed area; positive when the vertices wind
+    /// counter-clockwise in a y-up coordinate system.
+    ///
+    /// This is always exact, unlike [`Polygon::signed_area`], since lattice
+    /// polygons can have a half-integer area.
+    pub fn signed_double_area(&self) -> T {
+        // https://en.wikipedia.org/wiki/Shoelace_formula
+        self.edges().fold(T::zero(), |acc, ((x0, y0), (x1, y1))| {
+            acc + x0 * y1 - y0 * x1
+        })
+    }
+
+    /// The signed area, rounded toward zero.
+    pub fn signed_area(&self) -> T {
+        self.signed_double_area() / two()
+    }
+
+    /// The unsigned area, rounded toward zero.
+    pub fn area(&self) -> T {
+        self.signed_area().abs()
+    }
+
+    /// The number of lattice points on the polygon's edges.
+    pub fn boundary(&self) -> T {
+        self.edges().fold(T::zero(), |acc, ((x0, y0), (x1, y1))| {
+            acc + (x1 - x0).abs().gcd(&(y1 - y0).abs())
+        })
+    }
+
+    /// The number of lattice points strictly inside the polygon.
+    pub fn interior(&self) -> T {
+        // https://en.wikipedia.org/wiki/Pick%27s_theorem
+        //
+        //   A = i + b/2 - 1
+        //
+        // or, keeping everything integral
+        //
+        //   2i = 2A - b + 2
+        (self.signed_double_area().abs() - self.boundary() + two()) / two()
+    }
+
+    /// The number of lattice points inside or on the polygon, i.e. the
+    /// number of unit cells a loop drawn through cell centers covers.
+    pub fn enclosed(&self) -> T {
+        self.interior() + self.boundary()
+    }
+
+    fn edges(&self) -> impl Iterator<Item = ((T, T), (T, T))> + '_ {
+        self.vertices
+            .iter()
+            .copied()
+            .zip(self.vertices.iter().copied().cycle().skip(1))
+    }
+}
+
+fn two<T: Integer>() -> T {
+    T::one() + T::one()
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(feature = "test_lib")]
+mod tests {
+    use super::Polygon;
+
+    #[test]
+    fn closed_and_open_vertex_lists() {
+        let open = Polygon::new(vec![(0i64, 0), (3, 0), (3, 2), (0, 2)]);
+        let closed = Polygon::new(vec![(0i64, 0), (3, 0), (3, 2), (0, 2), (0, 0)]);
+        for polygon in [open, closed] {
+            assert_eq!(polygon.signed_area(), 6);
+            assert_eq!(polygon.boundary(), 10);
+            assert_eq!(polygon.interior(), 2);
+            assert_eq!(polygon.enclosed(), 12);
+        }
+    }
+
+    #[test]
+    fn winding_order() {
+        let ccw = Polygon::new(vec![(0i64, 0), (1, 0), (1, 1), (0, 1)]);
+        let cw = Polygon::new(vec![(0i64, 0), (0, 1), (1, 1), (1, 0)]);
+        assert_eq!(ccw.signed_area(), 1);
+        assert_eq!(cw.signed_area(), -1);
+        assert_eq!(ccw.area(), cw.area());
+    }
+
+    #[test]
+    fn diagonal_edges() {
+        let triangle = Polygon::new(vec![(0i64, 0), (4, 0), (0, 4)]);
+        assert_eq!(triangle.signed_double_area(), 16);
+        assert_eq!(triangle.boundary(), 12);
+        assert_eq!(triangle.interior(), 3);
+
+        let odd = Polygon::new(vec![(0i64, 0), (1, 0), (0, 1)]);
+        assert_eq!(odd.signed_double_area(), 1);
+        assert_eq!(odd.interior(), 0);
+    }
+
+    #[test]
+    fn from_steps() {
+        let steps = [((1i128, 0), 6), ((0, 1), 5), ((-1, 0), 6), ((0, -1), 5)];
+        let polygon = Polygon::from_steps(steps);
+        assert_eq!(polygon.vertices().len(), 5);
+        assert_eq!(polygon.enclosed(), 42);
+    }
+
+    #[test]
+    fn wide_coordinates() {
+        let big = 1i128 << 62;
+        let polygon = Polygon::from_steps([((1, 0), big), ((0, 1), big), ((-1, 0), big)]);
+        assert_eq!(polygon.area(), big * big);
+        assert_eq!(polygon.boundary(), 4 * big);
+    }
+}
+
+/* -------------------------------------------------------------------------- */
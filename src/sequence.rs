@@ -0,0 +1,233 @@
+use num::{BigInt, BigRational, ToPrimitive, Zero};
+use std::error::Error;
+use std::fmt::Display;
+
+/// The finite difference table of a sequence: the sequence itself, then
+/// the differences between its neighbors, then the differences of those,
+/// and so on, stopping at the first row that is all zeros or has a single
+/// value.
+///
+/// ```
+/// # use advent_of_code::sequence::differences;
+/// assert_eq!(
+///     differences(&[1, 3, 6, 10]),
+///     vec![vec![1, 3, 6, 10], vec![2, 3, 4], vec![1, 1], vec![0]],
+/// );
+/// ```
+pub fn differences(values: &[i64]) -> Vec<Vec<i64>> {
+    let mut table = vec![values.to_vec()];
+    loop {
+        let row = &table[table.len() - 1];
+        if row.len() <= 1 || row.iter().all(|value| *value == 0) {
+            break;
+        }
+        let next = row.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        table.push(next);
+    }
+    table
+}
+
+/// The value that would follow the sequence, taking it to be the lowest
+/// degree polynomial through all of its values, or [`None`] if it's empty.
+pub fn next(values: &[i64]) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+    let result = differences(values)
+        .iter()
+        .map(|row| row[row.len() - 1])
+        .sum();
+    Some(result)
+}
+
+/// The value that would precede the sequence, taking it to be the lowest
+/// degree polynomial through all of its values, or [`None`] if it's empty.
+pub fn previous(values: &[i64]) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+    let result = differences(values)
+        .iter()
+        .rev()
+        .fold(0, |acc, row| row[0] - acc);
+    Some(result)
+}
+
+/// A polynomial with rational coefficients, stored in Newton form so it
+/// can be built exactly from samples at any (distinct) points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polynomial {
+    xs: Vec<BigRational>,
+    coefficients: Vec<BigRational>,
+}
+
+impl Polynomial {
+    /// The lowest degree polynomial passing through every `(x, y)` sample.
+    pub fn interpolate(samples: &[(i64, i64)]) -> Result<Self, FitError> {
+        if samples.is_empty() {
+            return Err(FitError::TooFewSamples { needed: 1, got: 0 });
+        }
+        for (i, (x, _)) in samples.iter().enumerate() {
+            if samples[..i].iter().any(|(other, _)| other == x) {
+                return Err(FitError::DuplicateSample { x: *x });
+            }
+        }
+
+        // https://en.wikipedia.org/wiki/Newton_polynomial#Divided-Difference_Methods_vs._Lagrange
+        let xs: Vec<BigRational> = samples.iter().map(|(x, _)| rational(*x)).collect();
+        let mut coefficients: Vec<BigRational> =
+            samples.iter().map(|(_, y)| rational(*y)).collect();
+        for j in 1..coefficients.len() {
+            for i in (j..coefficients.len()).rev() {
+                coefficients[i] = (&coefficients[i] - &coefficients[i - 1]) / (&xs[i] - &xs[i - j]);
+            }
+        }
+        Ok(Self { xs, coefficients })
+    }
+
+    /// A polynomial of at most `degree` through every sample.
+    ///
+    /// The first `degree + 1` samples determine the polynomial, and the
+    /// rest are checked against it, so this fails if the samples don't
+    /// follow a polynomial of that degree.
+    pub fn fit(samples: &[(i64, i64)], degree: usize) -> Result<Self, FitError> {
+        if samples.len() <= degree {
+            return Err(FitError::TooFewSamples {
+                needed: degree + 1,
+                got: samples.len(),
+            });
+        }
+        let polynomial = Self::interpolate(&samples[..=degree])?;
+        for &(x, y) in &samples[degree + 1..] {
+            let expected = polynomial.eval(x);
+            if expected != rational(y) {
+                return Err(FitError::Mismatch { x, y, expected });
+            }
+        }
+        Ok(polynomial)
+    }
+
+    /// The polynomial's degree; zero for a constant, including zero.
+    pub fn degree(&self) -> usize {
+        self.coefficients
+            .iter()
+            .rposition(|coefficient| !coefficient.is_zero())
+            .unwrap_or_default()
+    }
+
+    /// Evaluates the polynomial at `x`.
+    pub fn eval(&self, x: i64) -> BigRational {
+        let x = rational(x);
+        let n = self.coefficients.len();
+        // Horner's method over the Newton basis
+        (0..n - 1)
+            .rev()
+            .fold(self.coefficients[n - 1].clone(), |acc, i| {
+                acc * (&x - &self.xs[i]) + &self.coefficients[i]
+            })
+    }
+
+    /// Evaluates the polynomial at `x`, returning [`None`] unless the result
+    /// is an integer that fits in an `i64`.
+    pub fn eval_integer(&self, x: i64) -> Option<i64> {
+        let value = self.eval(x);
+        if !value.is_integer() {
+            return None;
+        }
+        value.to_integer().to_i64()
+    }
+}
+
+fn rational(value: i64) -> BigRational {
+    BigRational::from_integer(BigInt::from(value))
+}
+
+/// An error which can be returned when fitting a [`Polynomial`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum FitError {
+    TooFewSamples {
+        needed: usize,
+        got: usize,
+    },
+    DuplicateSample {
+        x: i64,
+    },
+    Mismatch {
+        x: i64,
+        y: i64,
+        expected: BigRational,
+    },
+}
+
+impl Error for FitError {}
+
+impl Display for FitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooFewSamples { needed, got } => {
+                write!(f, "expecting at least {needed} samples, got {got}")
+            }
+            Self::DuplicateSample { x } => write!(f, "more than one sample at x = {x}"),
+            Self::Mismatch { x, y, expected } => {
+                write!(f, "sample at x = {x} is {y}, expecting {expected}")
+            }
+        }
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+#[cfg(feature = "test_lib")]
+mod tests {
+    use super::{next, previous, FitError, Polynomial};
+
+    #[test]
+    fn extrapolate() {
+        assert_eq!(next(&[0, 3, 6, 9, 12, 15]), Some(18));
+        assert_eq!(next(&[10, 13, 16, 21, 30, 45]), Some(68));
+        assert_eq!(previous(&[10, 13, 16, 21, 30, 45]), Some(5));
+        assert_eq!(next(&[7]), Some(7));
+        assert_eq!(next(&[]), None);
+    }
+
+    #[test]
+    fn interpolate_uneven_samples() {
+        // 3x² - x/2 + 1
+        let samples = [(-2, 14), (0, 1), (4, 47)];
+        let polynomial = Polynomial::interpolate(&samples).unwrap();
+        assert_eq!(polynomial.degree(), 2);
+        assert_eq!(polynomial.eval_integer(2), Some(12));
+        assert_eq!(polynomial.eval_integer(1), None);
+        assert_eq!(polynomial.eval(1).to_string(), "7/2");
+    }
+
+    #[test]
+    fn fit_checks_extra_samples() {
+        let squares: Vec<_> = (0..6).map(|x| (x, x * x)).collect();
+        let polynomial = Polynomial::fit(&squares, 2).unwrap();
+        assert_eq!(polynomial.eval_integer(1_000_000), Some(1_000_000_000_000));
+
+        assert!(matches!(
+            Polynomial::fit(&squares, 1),
+            Err(FitError::Mismatch { x: 2, y: 4, .. })
+        ));
+        assert_eq!(
+            Polynomial::fit(&squares[..2], 2),
+            Err(FitError::TooFewSamples { needed: 3, got: 2 })
+        );
+        assert_eq!(
+            Polynomial::interpolate(&[(1, 1), (1, 2)]),
+            Err(FitError::DuplicateSample { x: 1 })
+        );
+    }
+
+    #[test]
+    fn lower_degree_than_requested() {
+        let line = [(1, 5), (2, 7), (3, 9), (4, 11)];
+        let polynomial = Polynomial::fit(&line, 3).unwrap();
+        assert_eq!(polynomial.degree(), 1);
+        assert_eq!(polynomial.eval_integer(0), Some(3));
+    }
+}
+
+/* -------------------------------------------------------------------------- */
@@ -33,15 +33,28 @@ pub fn read_file_impl(part: Option<u8>, folder: &str, day: Day) -> String {
 }
 
 /// Creates the constant `DAY` and sets up the input and runner for each part.
+///
+/// Extra modes can be listed after the day, e.g. `solution!(20, "dot" => dot)`;
+/// running with `--mode dot` then prints what `dot` returns in place of the
+/// parts. A `--mode` naming none of them is an error.
 #[macro_export]
 macro_rules! solution {
     ($day:expr) => {
+        $crate::solution!($day,);
+    };
+    ($day:expr, $($mode:literal => $func:expr),* $(,)?) => {
         /// The current day.
         const DAY: advent_of_code::Day = advent_of_code::day!($day);
 
         fn main() {
             use advent_of_code::template::runner::*;
             let input = advent_of_code::template::read_file("inputs", DAY);
+            $(
+                if run_mode($mode, $func, &input) {
+                    return;
+                }
+            )*
+            reject_unknown_mode(&[$($mode),*]);
             run_part(part_one, &input, DAY, 1);
             run_part(part_two, &input, DAY, 2);
         }
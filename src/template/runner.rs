@@ -9,7 +9,31 @@ use std::{cmp, env, process};
 
 use super::ANSI_BOLD;
 
-pub fn run_part<I: Clone, T: Display>(func: impl Fn(I) -> Option<T>, input: I, day: Day, part: u8) {
+/// What a solution part returns: [`None`] or an [`Err`] mark the part as
+/// failed, and an error's message is printed alongside.
+pub trait Answer {
+    type Value: Display;
+
+    fn value(&self) -> Result<&Self::Value, Option<String>>;
+}
+
+impl<T: Display> Answer for Option<T> {
+    type Value = T;
+
+    fn value(&self) -> Result<&T, Option<String>> {
+        self.as_ref().ok_or(None)
+    }
+}
+
+impl<T: Display, E: Display> Answer for Result<T, E> {
+    type Value = T;
+
+    fn value(&self) -> Result<&T, Option<String>> {
+        self.as_ref().map_err(|err| Some(err.to_string()))
+    }
+}
+
+pub fn run_part<I: Clone, A: Answer>(func: impl Fn(I) -> A, input: I, day: Day, part: u8) {
     let part_str = format!("Part {part}");
 
     let (result, duration, samples) =
@@ -17,11 +41,58 @@ pub fn run_part<I: Clone, T: Display>(func: impl Fn(I) -> Option<T>, input: I, d
 
     print_result(&result, &part_str, &format_duration(&duration, samples));
 
-    if let Some(result) = result {
+    if let Ok(result) = result.value() {
         submit_result(result, day, part);
     }
 }
 
+/// The value passed with `--arg`, for modes that take one.
+pub fn mode_arg() -> Option<String> {
+    let args: Vec<String> = env::args().collect();
+    args.windows(2)
+        .find(|pair| pair[0] == "--arg")
+        .map(|pair| pair[1].clone())
+}
+
+/// Runs `func` in place of the solution parts if `--mode <mode>` was passed,
+/// returning whether it ran.
+pub fn run_mode<'a, A: Answer>(mode: &str, func: impl Fn(&'a str) -> A, input: &'a str) -> bool {
+    let args: Vec<String> = env::args().collect();
+    if !args
+        .windows(2)
+        .any(|pair| pair[0] == "--mode" && pair[1] == mode)
+    {
+        return false;
+    }
+
+    let result = func(input);
+    match result.value() {
+        Ok(value) => println!("{value}"),
+        Err(message) => println!("{mode}: ✖ {}", message.unwrap_or_default()),
+    }
+    true
+}
+
+/// Exits with an error listing `modes` if `--mode` was passed, since any mode
+/// that matched has already run by the time this is reached.
+pub fn reject_unknown_mode(modes: &[&str]) {
+    let args: Vec<String> = env::args().collect();
+    let Some(index) = args.iter().position(|arg| arg == "--mode") else {
+        return;
+    };
+
+    let available = if modes.is_empty() {
+        "none".to_string()
+    } else {
+        modes.join(", ")
+    };
+    match args.get(index + 1) {
+        Some(mode) => eprintln!("Unknown mode `{mode}`. Available modes: {available}"),
+        None => eprintln!("Missing mode name after --mode. Available modes: {available}"),
+    }
+    process::exit(1);
+}
+
 /// Run a solution part. The behavior differs depending on whether we are running a release or debug build:
 ///  1. in debug, the function is executed once.
 ///  2. in release, the function is benched (approx. 1 second of execution time or 10 samples, whatever take longer.)
@@ -92,11 +163,11 @@ fn format_duration(duration: &Duration, samples: u128) -> String {
     }
 }
 
-fn print_result<T: Display>(result: &Option<T>, part: &str, duration_str: &str) {
+fn print_result<A: Answer>(result: &A, part: &str, duration_str: &str) {
     let is_intermediate_result = duration_str.is_empty();
 
-    match result {
-        Some(result) => {
+    match result.value() {
+        Ok(result) => {
             if result.to_string().contains('\n') {
                 let str = format!("{part}: ▼ {duration_str}");
                 if is_intermediate_result {
@@ -116,12 +187,15 @@ fn print_result<T: Display>(result: &Option<T>, part: &str, duration_str: &str)
                 }
             }
         }
-        None => {
+        Err(message) => {
             if is_intermediate_result {
                 print!("{part}: ✖");
             } else {
                 print!("\r");
-                println!("{part}: ✖             ");
+                match message {
+                    Some(message) => println!("{part}: ✖ {message}             "),
+                    None => println!("{part}: ✖             "),
+                }
             }
         }
     }